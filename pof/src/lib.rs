@@ -2,10 +2,16 @@
 #![allow(clippy::useless_format)]
 
 mod parse;
+#[cfg(test)]
+mod tests;
 mod types;
 mod write;
 
 pub use parse::parse_dae;
 pub use parse::parse_gltf;
+pub use parse::parse_obj;
+pub use parse::ImportError;
+pub use parse::ImportWarning;
 pub use parse::Parser;
+pub use parse::OBJ_METADATA_EXTENSION;
 pub use types::*;
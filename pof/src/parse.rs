@@ -4,7 +4,7 @@ use core::panic;
 use dae_parser as dae;
 use glm::Mat4x4;
 use nalgebra_glm as glm;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
@@ -739,7 +739,7 @@ fn parse_bsp_data(mut buf: &[u8], version: Version) -> io::Result<BspData> {
         bsp_tree.recalculate_bboxes(&verts);
     }
 
-    Ok(BspData { collision_tree: bsp_tree, norms, verts })
+    Ok(BspData { collision_tree: bsp_tree, norms, verts, extras: None })
 }
 
 fn parse_shield_node(buf: &[u8], version: Version) -> io::Result<Box<ShieldNode>> {
@@ -770,6 +770,84 @@ fn parse_shield_node(buf: &[u8], version: Version) -> io::Result<Box<ShieldNode>
 // DAE / glTF parsing
 // =================================================================
 
+// something which stopped a file from being imported at all
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Dae(dae::Error),
+    Gltf(gltf::Error),
+    NoScene,
+    MissingReference(String), // something the file refers to, but which doesn't exist in it
+    InvalidData(String),      // the file is malformed in some way
+    BadBuffer(usize, String), // glTF buffer index, and what's wrong with it
+    NodeNotFound(String),     // no node by this name with any geometry
+    UnsupportedFileType(PathBuf),
+}
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "{}", err),
+            ImportError::Dae(err) => write!(f, "invalid COLLADA file: {:?}", err),
+            ImportError::Gltf(err) => write!(f, "invalid glTF file: {}", err),
+            ImportError::NoScene => write!(f, "the file has no scene"),
+            ImportError::MissingReference(what) => write!(f, "the file refers to {}, which it doesn't contain", what),
+            ImportError::InvalidData(what) => write!(f, "{}", what),
+            ImportError::BadBuffer(idx, what) => write!(f, "buffer {}: {}", idx, what),
+            ImportError::NodeNotFound(name) => write!(f, "no node named {} with any geometry", name),
+            ImportError::UnsupportedFileType(path) => write!(f, "{} is not a supported file type", path.display()),
+        }
+    }
+}
+impl std::error::Error for ImportError {}
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+impl From<dae::Error> for ImportError {
+    fn from(err: dae::Error) -> Self {
+        ImportError::Dae(err)
+    }
+}
+impl From<gltf::Error> for ImportError {
+    fn from(err: gltf::Error) -> Self {
+        ImportError::Gltf(err)
+    }
+}
+
+// something which was skipped or guessed at during import, but which didn't stop it
+#[derive(Debug, Clone)]
+pub enum ImportWarning {
+    NoNormals(String),              // a mesh without normals, which was skipped
+    NormalsCalculated(String),      // a mesh without normals, which had them calculated
+    UnnamedNode,                    // a node without a name, which was skipped along with its children
+    UnknownHelperNode(String),      // a # node which isn't recognized, which was skipped
+    UnnamedMaterial(usize),         // a material without a name, which was given a placeholder
+    UnknownMaterial(String),        // a material which doesn't exist, whose polygons were left untextured
+    UnsupportedGeometry(String),    // geometry on a node which isn't a triangle/polygon mesh, which was skipped
+    InvalidMetadata(usize, String), // a line of an OBJ's metadata file which couldn't be read, which was skipped
+    UnknownSubobject(String),       // metadata about a subobject which doesn't exist, which was skipped
+}
+impl std::fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportWarning::NoNormals(name) => write!(f, "{} has no normals, and was skipped", name),
+            ImportWarning::NormalsCalculated(name) => write!(f, "{} has no normals, so they were calculated", name),
+            ImportWarning::UnnamedNode => write!(f, "a node has no name, and was skipped along with its children"),
+            ImportWarning::UnknownHelperNode(name) => write!(f, "{} is not a recognized helper node, and was skipped", name),
+            ImportWarning::UnnamedMaterial(idx) => write!(f, "material {} has no name, and was named material{}", idx, idx),
+            ImportWarning::UnknownMaterial(name) => write!(f, "material {} doesn't exist, its polygons were left untextured", name),
+            ImportWarning::UnsupportedGeometry(name) => {
+                write!(f, "{} has geometry which isn't made of triangles or polygons, which was skipped", name)
+            }
+            ImportWarning::InvalidMetadata(line_num, line) => {
+                write!(f, "line {} of the metadata file couldn't be read, and was skipped: {}", line_num, line)
+            }
+            ImportWarning::UnknownSubobject(name) => write!(f, "the metadata refers to {}, which doesn't exist, and was skipped", name),
+        }
+    }
+}
+
 trait IsNode<'a>: Clone {
     type Children: Iterator<Item = Self>;
 
@@ -781,18 +859,87 @@ trait IsNode<'a>: Clone {
 
     // given a node, using its transforms return a position, normal and radius
     // things commonly needed by various pof points
-    fn parse_point(&self, &(mut transform): &Mat4x4, up: UpAxis) -> (Vec3d, Vec3d, f32) {
+    fn parse_point(&self, &(mut transform): &Mat4x4, coords: CoordSystem) -> (Vec3d, Vec3d, f32) {
         self.prepend_transforms(&mut transform);
         let zero = Vec3d::ZERO.into();
         let offset = transform.transform_point(&zero) - zero;
         let transform = transform.append_translation(&(-offset));
-        let pos = Vec3d::from(offset).from_coord(up);
-        let vector: Vec3d = transform.transform_point(&Vec3d::new(0., 0., 1.).to_coord(up).into()).into();
-        let radius = vector.magnitude();
-        let norm = vector.normalize().from_coord(up);
+        let pos = Vec3d::from(offset).from_coord(coords);
+        let vector: Vec3d = transform.transform_point(&Vec3d::new(0., 0., 1.).to_coord_dir(coords).into()).into();
+        let radius = vector.from_coord(coords).magnitude();
+        let norm = vector.normalize().from_coord_dir(coords);
         (pos, norm, radius)
     }
 
+    // metadata kept in the node itself (glTF extras) rather than in helper nodes, see NodeBuilder::push_metadata
+    fn extra<T: std::str::FromStr>(&self, _key: &str) -> Option<T> {
+        None
+    }
+
+    // a value attached with NodeBuilder::push_metadata, either from the extras or from its "#{prefix}{key}:{value}" helper node
+    fn metadata(&self, prefix: &str, key: &str) -> Option<String> {
+        self.extra(key).or_else(|| {
+            let id = format!("#{}{}", prefix, key);
+            self.children().find_map(|node| match node.name()?.split_once(':') {
+                Some((node_id, val)) if node_id == id => Some(val.to_string()),
+                _ => None,
+            })
+        })
+    }
+
+    // restores the header values written by make_header_node, over the ones recalculated from the geometry
+    fn parse_header(&self, model: &mut Model) {
+        let floats = |key| -> Option<Vec<f32>> { self.metadata("header-", key)?.split_whitespace().map(|val| val.parse().ok()).collect() };
+
+        if let Some(mass) = self.metadata("header-", "mass").and_then(|val| val.parse().ok()) {
+            model.header.mass = mass;
+        }
+        if let Some(&[x, y, z]) = floats("center-of-mass").as_deref() {
+            model.header.center_of_mass = Vec3d::new(x, y, z);
+        }
+        if let Some(&[rx, ry, rz, ux, uy, uz, fx, fy, fz]) = floats("moi").as_deref() {
+            model.header.moment_of_inertia = Mat3d {
+                rvec: Vec3d::new(rx, ry, rz),
+                uvec: Vec3d::new(ux, uy, uz),
+                fvec: Vec3d::new(fx, fy, fz),
+            };
+        }
+        if let Some(flags) = self.metadata("header-", "flags").and_then(|val| val.parse().ok()) {
+            model.header.obj_flags = flags;
+        }
+        if let Some(sections) = floats("cross-sections") {
+            model.header.cross_sections = sections.chunks_exact(2).map(|section| (section[0], section[1])).collect();
+        }
+        if let Some(lights) = floats("bsp-lights") {
+            model.header.bsp_lights = lights
+                .chunks_exact(4)
+                .filter_map(|light| {
+                    let kind = match light[3] as u32 {
+                        1 => BspLightKind::Muzzle,
+                        2 => BspLightKind::Thruster,
+                        _ => return None,
+                    };
+                    Some(BspLight { location: Vec3d::new(light[0], light[1], light[2]), kind })
+                })
+                .collect();
+        }
+        if let Some(comments) = self.metadata("header-", "comments") {
+            model.comments = unescape_metadata(&comments);
+        }
+    }
+
+    fn parse_subobj_extras(&self, subobj: &mut SubObject) {
+        if let Some(properties) = self.extra("properties") {
+            subobj.properties = properties;
+        }
+        if let Some(val) = self.extra::<i32>("mov-type") {
+            subobj.movement_type = val.try_into().unwrap_or_default();
+        }
+        if let Some(val) = self.extra::<i32>("mov-axis") {
+            subobj.movement_axis = val.try_into().unwrap_or_default();
+        }
+    }
+
     fn parse_properties(&self, properties: &mut String) {
         for node in self.children() {
             if let Some(name) = node.name() {
@@ -844,7 +991,7 @@ fn mk_insignia(detail_level: Option<u32>, offset: Vec3d, vertices: Vec<Vec3d>, p
 
 fn push_subobj(
     sub_objects: &mut Vec<SubObject>, offset: Vec3d, parent: Option<ObjectId>, name: &str, is_debris_model: bool, verts: Vec<Vec3d>,
-    norms: Vec<Vec3d>, polygons: Vec<(TextureId, Vec<PolyVertex>)>,
+    norms: Vec<Vec3d>, polygons: Vec<(TextureId, Vec<PolyVertex>)>, extras: Option<VertexExtras>,
 ) -> ObjectId {
     let obj_id = ObjectId(sub_objects.len() as _);
 
@@ -868,6 +1015,7 @@ fn push_subobj(
                     .map(|(texture, verts)| Polygon { normal: Default::default(), texture, verts }),
             ),
             verts,
+            extras,
         },
         children: Default::default(),
         is_debris_model,
@@ -880,44 +1028,56 @@ fn push_subobj(
     obj_id
 }
 
+// the vertices, normals and polygons of a mesh, and whatever else it had per vertex
+type ParsedGeometry = (Vec<Vec3d>, Vec<Vec3d>, Vec<(TextureId, Vec<PolyVertex>)>, Option<VertexExtras>);
+
 trait ParseCtx<'a> {
     type Node: IsNode<'a>;
-    fn up(&self) -> UpAxis {
-        UpAxis::YUp
-    }
+    fn coords(&self) -> CoordSystem;
 
-    fn parse_geometry(&self, node: &Self::Node, transform: &Mat4x4) -> (Vec<Vec3d>, Vec<Vec3d>, Vec<(TextureId, Vec<PolyVertex>)>);
+    fn parse_geometry(&self, node: &Self::Node, transform: &Mat4x4, warnings: &mut Vec<ImportWarning>) -> Result<ParsedGeometry, ImportError>;
 
-    fn parse_subobject_recursive(&self, model: &mut Model, node: Self::Node, parent: ObjectId, detail_level: Option<u32>, parent_transform: &Mat4x4) {
+    fn parse_subobject_recursive(
+        &self, model: &mut Model, node: Self::Node, parent: ObjectId, detail_level: Option<u32>, parent_transform: &Mat4x4,
+        warnings: &mut Vec<ImportWarning>,
+    ) -> Result<(), ImportError> {
         let name = match node.name() {
-            None => return, // subobjects must have names!
+            None => {
+                // subobjects must have names!
+                warnings.push(ImportWarning::UnnamedNode);
+                return Ok(());
+            }
             Some(name) => name,
         };
         let mut transform = parent_transform * node.transform();
         let zero = Vec3d::ZERO.into();
         let center = transform.transform_point(&zero) - zero;
         transform.append_translation_mut(&(-center));
-        let up = self.up();
-        let offset = Vec3d::from(center).from_coord(up);
+        let coords = self.coords();
+        let offset = Vec3d::from(center).from_coord(coords);
 
-        let (vertices_out, normals_out, polygons_out) = self.parse_geometry(&node, &transform);
+        let (vertices_out, normals_out, polygons_out, extras) = self.parse_geometry(&node, &transform, warnings)?;
 
         // ignore subobjects with no geo
-        // metadata (empties with names like #properties) are handled below directly
+        // metadata (empties with names like #properties) are handled below directly, so any that make it here aren't recognized
         // this function must *start* with a proper subobject
         if polygons_out.is_empty() {
-            return;
+            if name.starts_with('#') {
+                warnings.push(ImportWarning::UnknownHelperNode(name.to_string()));
+            }
+            return Ok(());
         }
 
         if name.to_lowercase().contains("insig") {
             model.insignias.push(mk_insignia(detail_level, offset, vertices_out, polygons_out));
         } else {
-            // this should probably be warned about...
             if vertices_out.is_empty() || normals_out.is_empty() {
-                return;
+                warnings.push(ImportWarning::NoNormals(name.to_string()));
+                return Ok(());
             }
 
-            let obj_id = push_subobj(&mut model.sub_objects, offset, Some(parent), name, false, vertices_out, normals_out, polygons_out);
+            let obj_id = push_subobj(&mut model.sub_objects, offset, Some(parent), name, false, vertices_out, normals_out, polygons_out, extras);
+            node.parse_subobj_extras(&mut model.sub_objects[obj_id]);
 
             for node in node.children() {
                 // make a pointer to the subobj we just pushed
@@ -942,7 +1102,7 @@ trait ParseCtx<'a> {
                         turret.gun_obj = obj_id;
                         turret.base_obj = if name.contains("gun") { parent } else { obj_id };
 
-                        let (pos, norm, _) = node.parse_point(parent_transform, up);
+                        let (pos, norm, _) = node.parse_point(parent_transform, coords);
                         turret.fire_points.push(pos);
                         turret.normal = norm.try_into().unwrap_or_default();
                         continue;
@@ -966,109 +1126,100 @@ trait ParseCtx<'a> {
                     }
                 }
 
-                self.parse_subobject_recursive(model, node, obj_id, detail_level, &transform);
+                self.parse_subobject_recursive(model, node, obj_id, detail_level, &transform, warnings)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // searches the whole hierarchy for a node with the given name, and parses its geometry relative to its own origin
+    fn parse_named_node_geometry(
+        &self, nodes: impl IntoIterator<Item = Self::Node>, node_name: &str, parent_transform: &Mat4x4, warnings: &mut Vec<ImportWarning>,
+    ) -> Result<Option<ParsedGeometry>, ImportError> {
+        for node in nodes {
+            let mut transform = parent_transform * node.transform();
+            if node.name() == Some(node_name) {
+                let zero = Vec3d::ZERO.into();
+                let center = transform.transform_point(&zero) - zero;
+                transform.append_translation_mut(&(-center));
+                return self.parse_geometry(&node, &transform, warnings).map(Some);
+            }
+            if let Some(geometry) = self.parse_named_node_geometry(node.children(), node_name, &transform, warnings)? {
+                return Ok(Some(geometry));
             }
         }
+        Ok(None)
     }
 
-    fn parse_top_level_nodes(&self, model: &mut Model, nodes: impl IntoIterator<Item = Self::Node>) {
+    fn parse_top_level_nodes(
+        &self, model: &mut Model, nodes: impl IntoIterator<Item = Self::Node>, warnings: &mut Vec<ImportWarning>,
+    ) -> Result<(), ImportError> {
+        // path points refer to turrets by name, which can only be looked up once all the subobjects are in
+        let mut path_point_turrets: Vec<(usize, usize, String)> = vec![];
+        // and the header has to wait until the values it replaces have been recalculated
+        let mut header_node = None;
+
         for node in nodes {
             let mut transform = node.transform();
             let zero = Vec3d::ZERO.into();
             let center = transform.transform_point(&zero) - zero;
             transform.append_translation_mut(&(-center));
-            let up = self.up();
-            let offset = Vec3d::from(center).from_coord(up);
+            let coords = self.coords();
+            let offset = Vec3d::from(center).from_coord(coords);
 
             let name = match node.name() {
                 Some(name) => name,
-                None => continue,
+                None => {
+                    warnings.push(ImportWarning::UnnamedNode);
+                    continue;
+                }
             };
 
-            let (vertices_out, normals_out, polygons_out) = self.parse_geometry(&node, &transform);
+            let (vertices_out, normals_out, polygons_out, extras) = self.parse_geometry(&node, &transform, warnings)?;
             if !polygons_out.is_empty() {
                 if name.to_lowercase() == "shield" {
-                    let mut polygons = vec![];
-                    for (_, verts) in polygons_out {
-                        let verts = verts.into_iter().map(|poly| poly.vertex_id).collect::<Vec<_>>();
-                        // triangulate, just to be sure
-                        if let [vert1, ref rest @ ..] = *verts {
-                            for slice in rest.windows(2) {
-                                if let [vert2, vert3] = *slice {
-                                    let [v1, v2, v3] = [vert1, vert2, vert3].map(|i| nalgebra_glm::Vec3::from(vertices_out[i.0 as usize]));
-                                    polygons.push(ShieldPolygon {
-                                        normal: (v2 - v1).cross(&(v3 - v1)).normalize().into(),
-                                        verts: (vert1, vert2, vert3),
-                                        neighbors: Default::default(),
-                                    })
-                                }
-                            }
-                        }
-                    }
-
-                    // assign shield neighbors
-                    // create a map keyed on each vertex pair, based on winding order, where the value is the polygon id
-                    let mut map: HashMap<(VertexId, VertexId), PolygonId> = HashMap::new();
-                    for (i, poly) in polygons.iter().enumerate() {
-                        map.insert((poly.verts.0, poly.verts.1), PolygonId(i as u32));
-                        map.insert((poly.verts.1, poly.verts.2), PolygonId(i as u32));
-                        map.insert((poly.verts.2, poly.verts.0), PolygonId(i as u32));
-                    }
-
-                    // for each polygon then, by swapping its vertex pairs, you can grab each adjacent polygon
-                    for poly in &mut polygons {
-                        let neighbor1 = map.get(&(poly.verts.1, poly.verts.0)).unwrap_or(&PolygonId(0));
-                        let neighbor2 = map.get(&(poly.verts.2, poly.verts.1)).unwrap_or(&PolygonId(0));
-                        let neighbor3 = map.get(&(poly.verts.0, poly.verts.2)).unwrap_or(&PolygonId(0));
-                        poly.neighbors = (*neighbor1, *neighbor2, *neighbor3);
-                    }
-                    // a map insertion where an entry already exists or a failure to get from the map indicate non-manifoldness, TODO maybe indicate that
-
-                    model.shield_data = Some(ShieldData {
-                        collision_tree: Some(ShieldData::recalculate_tree(&vertices_out, &polygons)),
-                        verts: vertices_out,
-                        polygons,
-                    });
+                    model.shield_data = Some(mk_shield(vertices_out, polygons_out));
                 } else if name.to_lowercase().contains("insig") {
                     model.insignias.push(mk_insignia(None, offset, vertices_out, polygons_out));
                 } else {
                     // must be a subobject
 
-                    // this should probably be warned about...
                     if vertices_out.is_empty() || normals_out.is_empty() {
+                        warnings.push(ImportWarning::NoNormals(name.to_string()));
                         continue;
                     }
 
-                    let obj_id =
-                        push_subobj(&mut model.sub_objects, offset, None, name, name.starts_with("debris"), vertices_out, normals_out, polygons_out);
+                    let obj_id = push_subobj(
+                        &mut model.sub_objects,
+                        offset,
+                        None,
+                        name,
+                        name.starts_with("debris"),
+                        vertices_out,
+                        normals_out,
+                        polygons_out,
+                        extras,
+                    );
 
-                    let mut detail_level: Option<u32> = None;
-                    if let Some(idx) = name.to_lowercase().find("detail") {
-                        if let Ok(level) = name[(idx + 6)..].parse::<usize>() {
-                            if level >= model.header.detail_levels.len() {
-                                model.header.detail_levels.resize(level + 1, obj_id);
-                            } else {
-                                model.header.detail_levels[level] = obj_id;
-                            }
-                            detail_level = Some(level as u32);
-                        }
-                    }
+                    node.parse_subobj_extras(&mut model.sub_objects[obj_id]);
+                    let detail_level = register_detail_level(model, name, obj_id);
 
                     for node in node.children() {
-                        self.parse_subobject_recursive(model, node, obj_id, detail_level, &transform);
+                        self.parse_subobject_recursive(model, node, obj_id, detail_level, &transform, warnings)?;
                     }
                 }
             } else if name == "#thrusters" {
                 for (node, _) in node_children_with_keyword(node, "bank") {
                     let mut new_bank = ThrusterBank::default();
 
-                    for (node, name) in node_children_with_keyword(node, "") {
+                    for (node, name) in node_children_with_keyword(node.clone(), "") {
                         if name.contains("properties") {
                             node.parse_properties(&mut new_bank.properties);
                         } else if name.contains("point") {
                             let mut new_point = ThrusterGlow::default();
 
-                            let (pos, norm, rad) = node.parse_point(&transform, up);
+                            let (pos, norm, rad) = node.parse_point(&transform, coords);
                             new_point.position = pos;
                             new_point.normal = norm;
                             new_point.radius = rad;
@@ -1077,13 +1228,17 @@ trait ParseCtx<'a> {
                         }
                     }
 
+                    if let Some(properties) = node.extra("properties") {
+                        new_bank.properties = properties;
+                    }
+
                     model.thruster_banks.push(new_bank);
                 }
             } else if name == "#paths" {
                 for (node, _) in node_children_with_keyword(node, "path") {
                     let mut new_path = Path::default();
 
-                    for (node, name) in node_children_with_keyword(node, "") {
+                    for (node, name) in node_children_with_keyword(node.clone(), "") {
                         if name.contains("parent") {
                             if let Some(idx) = name.find(':') {
                                 new_path.parent = format!("{}", &name[(idx + 1)..]);
@@ -1095,14 +1250,30 @@ trait ParseCtx<'a> {
                         } else if name.contains("point") {
                             let mut new_point = PathPoint::default();
 
-                            let (pos, _, rad) = node.parse_point(&transform, up);
+                            let (pos, _, rad) = node.parse_point(&transform, coords);
                             new_point.position = pos;
                             new_point.radius = rad;
 
+                            for (_, name) in node_children_with_keyword(node.clone(), "turrets") {
+                                if let Some(idx) = name.find(':') {
+                                    path_point_turrets.push((model.paths.len(), new_path.points.len(), name[(idx + 1)..].to_string()));
+                                }
+                            }
+                            if let Some(names) = node.extra("turrets") {
+                                path_point_turrets.push((model.paths.len(), new_path.points.len(), names));
+                            }
+
                             new_path.points.push(new_point);
                         }
                     }
 
+                    if let Some(name) = node.extra("name") {
+                        new_path.name = name;
+                    }
+                    if let Some(parent) = node.extra("parent") {
+                        new_path.parent = parent;
+                    }
+
                     model.paths.push(new_path);
                 }
             } else if name.starts_with("#") && name.contains("weapons") {
@@ -1112,11 +1283,11 @@ trait ParseCtx<'a> {
                     for (node, _) in node_children_with_keyword(node, "point") {
                         let mut new_point = WeaponHardpoint::default();
 
-                        let (pos, norm, _) = node.parse_point(&transform, up);
+                        let (pos, norm, _) = node.parse_point(&transform, coords);
                         new_point.position = pos;
                         new_point.normal = norm.try_into().unwrap_or_default();
 
-                        for (_, name) in node_children_with_keyword(node, "offset") {
+                        for (_, name) in node_children_with_keyword(node.clone(), "offset") {
                             if let Some(idx) = name.find(":") {
                                 if let Ok(val) = &name[(idx + 1)..].parse() {
                                     new_point.offset = *val;
@@ -1124,6 +1295,10 @@ trait ParseCtx<'a> {
                                 }
                             }
                         }
+                        if let Some(offset) = node.extra("offset") {
+                            new_point.offset = offset;
+                        }
+                        new_point.offset *= coords.scale();
 
                         new_bank.push(new_point);
                     }
@@ -1140,14 +1315,14 @@ trait ParseCtx<'a> {
 
                     let transform = node.transform();
                     let zero = Vec3d::ZERO.into();
-                    new_bay.position = Vec3d::from(transform.transform_point(&zero) - zero).from_coord(up);
+                    new_bay.position = Vec3d::from(transform.transform_point(&zero) - zero).from_coord(coords);
                     new_bay.fvec = transform.transform_vector(&glm::vec3(0., 1., 0.)).try_into().unwrap_or_default();
-                    new_bay.fvec.0 = new_bay.fvec.0.from_coord(up);
+                    new_bay.fvec.0 = new_bay.fvec.0.from_coord_dir(coords);
 
                     new_bay.uvec = transform.transform_vector(&glm::vec3(0., 0., 1.)).try_into().unwrap_or_default();
-                    new_bay.uvec.0 = new_bay.uvec.0.from_coord(up);
+                    new_bay.uvec.0 = new_bay.uvec.0.from_coord_dir(coords);
 
-                    for (node, name) in node_children_with_keyword(node, "") {
+                    for (node, name) in node_children_with_keyword(node.clone(), "") {
                         if name.contains("properties") {
                             node.parse_properties(&mut new_bay.properties);
                         } else if name.contains("path") {
@@ -1159,13 +1334,20 @@ trait ParseCtx<'a> {
                         }
                     }
 
+                    if let Some(properties) = node.extra("properties") {
+                        new_bay.properties = properties;
+                    }
+                    if let Some(path) = node.extra("path") {
+                        new_bay.path = Some(PathId(path));
+                    }
+
                     model.docking_bays.push(new_bay);
                 }
             } else if name == "#glows" {
                 for (node, _) in node_children_with_keyword(node, "glowbank") {
                     let mut new_bank = GlowPointBank::default();
 
-                    for (node, name) in node_children_with_keyword(node, "") {
+                    for (node, name) in node_children_with_keyword(node.clone(), "") {
                         if name.contains("type") {
                             if let Some(idx) = name.find(":") {
                                 if let Ok(val) = &name[(idx + 1)..].parse() {
@@ -1207,7 +1389,7 @@ trait ParseCtx<'a> {
                         } else if name.contains("point") {
                             let mut new_point = GlowPoint::default();
 
-                            let (pos, norm, rad) = node.parse_point(&transform, up);
+                            let (pos, norm, rad) = node.parse_point(&transform, coords);
                             new_point.position = pos;
                             new_point.normal = if name.contains("omni") { Vec3d::ZERO } else { norm };
                             new_point.radius = rad;
@@ -1216,6 +1398,28 @@ trait ParseCtx<'a> {
                         }
                     }
 
+                    if let Some(val) = node.extra("type") {
+                        new_bank.glow_type = val;
+                    }
+                    if let Some(val) = node.extra("lod") {
+                        new_bank.lod = val;
+                    }
+                    if let Some(val) = node.extra("parent") {
+                        new_bank.obj_parent = ObjectId(val);
+                    }
+                    if let Some(val) = node.extra("ontime") {
+                        new_bank.on_time = val;
+                    }
+                    if let Some(val) = node.extra("offtime") {
+                        new_bank.off_time = val;
+                    }
+                    if let Some(val) = node.extra("disptime") {
+                        new_bank.disp_time = val;
+                    }
+                    if let Some(properties) = node.extra("properties") {
+                        new_bank.properties = properties;
+                    }
+
                     model.glow_banks.push(new_bank);
                 }
             } else if name == "#special points" {
@@ -1226,13 +1430,16 @@ trait ParseCtx<'a> {
                         new_point.name = format!("{}", &name[(idx + 1)..]);
                     }
 
-                    let (pos, _, rad) = node.parse_point(&transform, up);
+                    let (pos, _, rad) = node.parse_point(&transform, coords);
                     new_point.position = pos;
                     new_point.radius = rad;
 
-                    for (node, _) in node_children_with_keyword(node, "properties") {
+                    for (node, _) in node_children_with_keyword(node.clone(), "properties") {
                         node.parse_properties(&mut new_point.properties);
                     }
+                    if let Some(properties) = node.extra("properties") {
+                        new_point.properties = properties;
+                    }
 
                     model.special_points.push(new_point);
                 }
@@ -1240,11 +1447,11 @@ trait ParseCtx<'a> {
                 for (node, _) in node_children_with_keyword(node, "point") {
                     let mut new_point = EyePoint::default();
 
-                    let (pos, norm, _) = node.parse_point(&transform, up);
+                    let (pos, norm, _) = node.parse_point(&transform, coords);
                     new_point.offset = pos;
                     new_point.normal = norm.try_into().unwrap_or_default();
 
-                    for (_, name) in node_children_with_keyword(node, "parent") {
+                    for (_, name) in node_children_with_keyword(node.clone(), "parent") {
                         if let Some(idx) = name.find(":") {
                             if let Ok(val) = &name[(idx + 1)..].parse() {
                                 new_point.attached_subobj = ObjectId(*val);
@@ -1252,36 +1459,103 @@ trait ParseCtx<'a> {
                             }
                         }
                     }
+                    if let Some(val) = node.extra("parent") {
+                        new_point.attached_subobj = ObjectId(val);
+                    }
 
                     model.eye_points.push(new_point);
                 }
             } else if name == "#visual-center" {
-                let (pos, _, _) = node.parse_point(&transform, up);
+                let (pos, _, _) = node.parse_point(&transform, coords);
                 model.visual_center = pos;
+            } else if name == "#header" {
+                header_node = Some(node.clone());
+            } else if name.starts_with('#') {
+                warnings.push(ImportWarning::UnknownHelperNode(name.to_string()));
             }
         }
 
-        for i in 0..model.sub_objects.len() {
-            if let Some(parent) = model.sub_objects[ObjectId(i as u32)].parent {
-                let id = model.sub_objects[ObjectId(i as u32)].obj_id;
-                model.sub_objects[parent].children.push(id);
+        for (path_idx, point_idx, names) in path_point_turrets {
+            for name in names.split(',').filter(|name| !name.is_empty()) {
+                match model.get_obj_id_by_name(name) {
+                    Some(id) => model.paths[path_idx].points[point_idx].turrets.push(id),
+                    None => warnings.push(ImportWarning::UnknownSubobject(name.to_string())),
+                }
             }
         }
 
-        if model.header.detail_levels.is_empty() && !model.sub_objects.is_empty() {
-            model.header.detail_levels.push(ObjectId(0));
-            // this is pretty bad, but not having any detail levels is worse
+        finish_import(model);
+
+        if let Some(node) = header_node {
+            node.parse_header(model);
+        }
+
+        Ok(())
+    }
+}
+
+// builds the shield out of some imported geometry
+fn mk_shield(vertices_out: Vec<Vec3d>, polygons_out: Vec<(TextureId, Vec<PolyVertex>)>) -> ShieldData {
+    let mut polygons = vec![];
+    for (_, verts) in polygons_out {
+        let verts = verts.into_iter().map(|poly| poly.vertex_id).collect::<Vec<_>>();
+        // triangulate, just to be sure
+        if let [vert1, ref rest @ ..] = *verts {
+            for slice in rest.windows(2) {
+                if let [vert2, vert3] = *slice {
+                    let [v1, v2, v3] = [vert1, vert2, vert3].map(|i| nalgebra_glm::Vec3::from(vertices_out[i.0 as usize]));
+                    polygons.push(ShieldPolygon {
+                        normal: (v2 - v1).cross(&(v3 - v1)).normalize().into(),
+                        verts: (vert1, vert2, vert3),
+                        neighbors: Default::default(),
+                    })
+                }
+            }
         }
+    }
 
-        model.header.num_subobjects = model.sub_objects.len() as _;
+    // open or duplicated edges are left as is, they can be checked and repaired later
+    let mut shield_data = ShieldData { verts: vertices_out, polygons, collision_tree: None };
+    shield_data.recalc_neighbors();
+    shield_data.collision_tree = Some(ShieldData::recalculate_tree(&shield_data.verts, &shield_data.polygons));
+    shield_data
+}
+
+// if the subobject is named like a detail level (detail0, detail1...), makes it that detail level, and returns which one it is
+fn register_detail_level(model: &mut Model, name: &str, obj_id: ObjectId) -> Option<u32> {
+    let idx = name.to_lowercase().find("detail")?;
+    let level = name[(idx + 6)..].parse::<usize>().ok()?;
+    if level >= model.header.detail_levels.len() {
+        model.header.detail_levels.resize(level + 1, obj_id);
+    } else {
+        model.header.detail_levels[level] = obj_id;
+    }
+    Some(level as u32)
+}
 
-        model.untextured_idx = post_parse_fill_untextured_slot(&mut model.sub_objects, &mut model.textures);
+// fills in everything that can be derived, once all the subobjects (with their parents) have been imported
+fn finish_import(model: &mut Model) {
+    for i in 0..model.sub_objects.len() {
+        if let Some(parent) = model.sub_objects[ObjectId(i as u32)].parent {
+            let id = model.sub_objects[ObjectId(i as u32)].obj_id;
+            model.sub_objects[parent].children.push(id);
+        }
+    }
 
-        model.recalc_radius();
-        model.recalc_bbox();
-        model.recalc_mass();
-        model.recalc_moi();
+    if model.header.detail_levels.is_empty() && !model.sub_objects.is_empty() {
+        model.header.detail_levels.push(ObjectId(0));
+        // this is pretty bad, but not having any detail levels is worse
     }
+
+    model.header.num_subobjects = model.sub_objects.len() as _;
+
+    model.untextured_idx = post_parse_fill_untextured_slot(&mut model.sub_objects, &mut model.textures);
+
+    model.recalc_radius();
+    model.recalc_bbox();
+    model.recalc_mass();
+    model.recalc_center_of_mass(CenterOfMassMode::Volume);
+    model.recalc_moi();
 }
 
 // =================================================================
@@ -1307,19 +1581,21 @@ impl<'a> IsNode<'a> for &'a dae::Node {
 struct DaeContext<'a> {
     local_maps: dae::LocalMaps<'a>,
     material_map: HashMap<&'a String, TextureId>,
-    up: UpAxis,
+    coords: CoordSystem,
 }
 
 impl<'a> ParseCtx<'a> for DaeContext<'a> {
     type Node = &'a dae::Node;
-    fn up(&self) -> UpAxis {
-        self.up
+    fn coords(&self) -> CoordSystem {
+        self.coords
     }
-    fn parse_geometry(&self, node: &&'a dae::Node, transform: &Mat4x4) -> (Vec<Vec3d>, Vec<Vec3d>, Vec<(TextureId, Vec<PolyVertex>)>) {
+    fn parse_geometry(&self, node: &&'a dae::Node, transform: &Mat4x4, warnings: &mut Vec<ImportWarning>) -> Result<ParsedGeometry, ImportError> {
         let mut vertices_out: Vec<Vec3d> = vec![];
         let mut normals_out: Vec<Vec3d> = vec![];
         let mut normals_map: HashMap<Vec3d, NormalId> = HashMap::new();
         let mut polygons_out = vec![];
+        let mut extras = VertexExtras::default();
+        let node_name = node.name.as_deref().unwrap_or_default();
 
         for geo in &node.instance_geometry {
             use dae::source::{SourceReader, ST, XYZ};
@@ -1329,123 +1605,230 @@ impl<'a> ParseCtx<'a> for DaeContext<'a> {
                 normal_ids: Vec<NormalId>,
             }
 
-            impl<'a> dae::geom::VertexLoad<'a, VertexContext> for PolyVertex {
+            // the first uv set goes in the polygon vertex, any others are sorted into the extras once it's complete
+            #[derive(Clone)]
+            struct DaeVertex(PolyVertex, Vec<(usize, (f32, f32))>);
+
+            impl<'a> dae::geom::VertexLoad<'a, VertexContext> for DaeVertex {
                 fn position(ctx: &VertexContext, _: &SourceReader<'a, XYZ>, index: u32) -> Self {
-                    PolyVertex {
+                    let polyvert = PolyVertex {
                         vertex_id: VertexId(index + ctx.vertex_offset),
                         normal_id: NormalId(0),
                         uv: (0.0, 0.0),
-                    }
+                    };
+                    DaeVertex(polyvert, vec![])
                 }
                 fn add_normal(&mut self, ctx: &VertexContext, _: &SourceReader<'a, XYZ>, index: u32) {
-                    self.normal_id = ctx.normal_ids[index as usize];
+                    self.0.normal_id = ctx.normal_ids[index as usize];
                 }
                 fn add_texcoord(&mut self, _: &VertexContext, reader: &SourceReader<'a, ST>, index: u32, set: Option<u32>) {
-                    assert!(set.map_or(true, |set| set == 0));
                     let [u, v] = reader.get(index as usize);
-                    self.uv = (u, v);
+                    match set {
+                        None | Some(0) => self.0.uv = (u, v),
+                        Some(set) => self.1.push((set as usize - 1, (u, v))),
+                    }
                 }
             }
 
-            let geo = self.local_maps[&geo.url].element.as_mesh().unwrap();
-            let verts = geo.vertices.as_ref().unwrap().importer(&self.local_maps).unwrap();
+            // the k-th vertex read from a primitive has the k-th color, if any
+            let mut finish_vertex = |DaeVertex(polyvert, uvs): DaeVertex, color: Option<[f32; 4]>| {
+                for (set, uv) in uvs {
+                    if extras.uv_sets.len() <= set {
+                        extras.uv_sets.resize_with(set + 1, Default::default);
+                    }
+                    extras.uv_sets[set].insert(polyvert.key(), uv);
+                }
+                if let Some(color) = color {
+                    extras.colors.insert(polyvert.key(), color);
+                }
+                polyvert
+            };
+
+            let missing = |what: &str| ImportError::MissingReference(format!("{} of {}", what, geo.url));
+            let geo_elem = self.local_maps.get(&geo.url).ok_or_else(|| missing("the geometry"))?;
+            let geo = match geo_elem.element.as_mesh() {
+                Some(mesh) => mesh,
+                None => {
+                    warnings.push(ImportWarning::UnsupportedGeometry(node_name.to_string()));
+                    continue;
+                }
+            };
+            let verts = geo
+                .vertices
+                .as_ref()
+                .ok_or_else(|| ImportError::InvalidData(format!("a mesh of {} has no vertices", node_name)))?
+                .importer(&self.local_maps)
+                .map_err(|_| missing("the vertex data"))?;
             let mut vert_ctx = VertexContext { vertex_offset: vertices_out.len() as u32, normal_ids: vec![] };
 
-            for position in Clone::clone(verts.position_importer().unwrap()).map(Vec3d::from) {
-                vertices_out.push((transform * position).from_coord(self.up));
+            let position_importer = verts
+                .position_importer()
+                .ok_or_else(|| ImportError::InvalidData(format!("a mesh of {} has no vertex positions", node_name)))?;
+            for position in Clone::clone(position_importer).map(Vec3d::from) {
+                vertices_out.push((transform * position).from_coord(self.coords));
             }
 
+            let material_texture = |material: &Option<String>, warnings: &mut Vec<ImportWarning>| match material {
+                Some(mat) => self.material_map.get(mat).copied().unwrap_or_else(|| {
+                    warnings.push(ImportWarning::UnknownMaterial(mat.clone()));
+                    TextureId::UNTEXTURED
+                }),
+                None => TextureId::UNTEXTURED,
+            };
+
             for prim_elem in &geo.elements {
                 match prim_elem {
                     dae::Primitive::PolyList(polies) => {
                         // println!("{:#?}, {:#?}", polies.material, material_map);
-                        let texture = match &polies.material {
-                            Some(mat) => self.material_map[mat],
-                            None => TextureId::UNTEXTURED,
-                        };
+                        let texture = material_texture(&polies.material, warnings);
 
-                        let importer = polies.importer(&self.local_maps, verts.clone()).unwrap();
+                        let importer = polies
+                            .importer(&self.local_maps, verts.clone())
+                            .map_err(|_| missing("the polygon data"))?;
 
                         vert_ctx.normal_ids = vec![];
                         if let Some(normal_importer) = importer.normal_importer() {
                             for normal in Clone::clone(normal_importer).map(Vec3d::from) {
                                 vert_ctx.normal_ids.push(*normals_map.entry(normal).or_insert_with(|| {
                                     let id = NormalId(normals_out.len().try_into().unwrap());
-                                    normals_out.push((transform * normal).from_coord(self.up));
+                                    normals_out.push((transform * normal).from_coord_dir(self.coords));
                                     id
                                 }));
                             }
                         }
 
-                        let mut iter = importer.read::<_, PolyVertex>(&vert_ctx, &polies.data.prim);
+                        let mut iter = importer.read::<_, DaeVertex>(&vert_ctx, &polies.data.prim);
+                        let mut colors = self.read_colors(&polies.inputs, &polies.data.prim).into_iter().flatten();
 
                         for &n in &*polies.data.vcount {
-                            let verts = (0..n).map(|_| iter.next().unwrap()).collect();
+                            let verts = (0..n)
+                                .map(|_| Some(finish_vertex(iter.next()?, colors.next())))
+                                .collect::<Option<_>>()
+                                .ok_or_else(|| ImportError::InvalidData(format!("a polygon list of {} is cut short", node_name)))?;
                             polygons_out.push((texture, verts));
                         }
                     }
                     dae::Primitive::Triangles(tris) => {
                         // println!("{:#?}, {:#?}", tris.material, material_map);
-                        let texture = match &tris.material {
-                            Some(mat) => self.material_map[mat],
-                            None => TextureId::UNTEXTURED,
-                        };
-                        let importer = tris.importer(&self.local_maps, verts.clone()).unwrap();
+                        let texture = material_texture(&tris.material, warnings);
+                        let importer = tris.importer(&self.local_maps, verts.clone()).map_err(|_| missing("the triangle data"))?;
 
                         vert_ctx.normal_ids = vec![];
                         if let Some(normal_importer) = importer.normal_importer() {
                             for normal in Clone::clone(normal_importer) {
                                 vert_ctx.normal_ids.push(*normals_map.entry(normal.into()).or_insert_with(|| {
                                     let id = NormalId(normals_out.len().try_into().unwrap());
-                                    normals_out.push((transform * Vec3d::from(normal)).from_coord(self.up));
+                                    normals_out.push((transform * Vec3d::from(normal)).from_coord_dir(self.coords));
                                     id
                                 }));
                             }
                         }
 
-                        let mut iter = importer.read::<_, PolyVertex>(&vert_ctx, tris.data.prim.as_ref().unwrap());
-                        while let Some(vert1) = iter.next() {
-                            polygons_out.push((texture, vec![vert1, iter.next().unwrap(), iter.next().unwrap()]));
+                        if let Some(prim) = &tris.data.prim {
+                            let mut colors = self.read_colors(&tris.inputs, prim).into_iter().flatten();
+                            let mut iter = importer
+                                .read::<_, DaeVertex>(&vert_ctx, prim)
+                                .map(|vert| finish_vertex(vert, colors.next()));
+                            while let (Some(vert1), Some(vert2), Some(vert3)) = (iter.next(), iter.next(), iter.next()) {
+                                polygons_out.push((texture, vec![vert1, vert2, vert3]));
+                            }
                         }
                     }
-                    _ => {}
+                    _ => warnings.push(ImportWarning::UnsupportedGeometry(node_name.to_string())),
                 }
             }
         }
 
-        for poly in &mut polygons_out {
-            poly.1.reverse(); // normal facing (which is determined by winding order) is inverted for FSO
+        if !self.coords.flip_winding() {
+            for poly in &mut polygons_out {
+                poly.1.reverse(); // normal facing (which is determined by winding order) is inverted for FSO
+            }
+        }
+
+        let extras = if extras.is_empty() { None } else { Some(extras) };
+        Ok((vertices_out, normals_out, polygons_out, extras))
+    }
+}
+
+impl<'a> DaeContext<'a> {
+    // dae-parser doesn't read vertex colors, so this pulls one for each of a primitive's vertices straight from the source
+    // RGB colors are given an alpha of 1
+    fn read_colors(&self, inputs: &dae::InputList, prim: &[u32]) -> Option<Vec<[f32; 4]>> {
+        let input = inputs
+            .iter()
+            .filter(|input| input.semantic == dae::Semantic::Color)
+            .min_by_key(|input| input.set)?;
+        let source = self.local_maps.get(input.source_as_source())?;
+        let values = match source.array.as_ref()? {
+            dae::ArrayElement::Float(array) => &array.val,
+            _ => return None,
+        };
+        let components = source.accessor.param.len().min(4);
+        let colors = prim
+            .chunks_exact(inputs.stride)
+            .map(|indices| {
+                let start = source.accessor.offset + indices[input.offset as usize] as usize * source.accessor.stride;
+                let mut color = [1.0; 4];
+                for (out, &value) in color.iter_mut().zip(values.get(start..).unwrap_or_default().iter().take(components)) {
+                    *out = value;
+                }
+                color
+            })
+            .collect();
+        Some(colors)
+    }
+
+    // also returns the texture names, indexed by the texture ids the context will produce
+    fn new(document: &'a dae::Document, options: &ImportOptions) -> (Self, Vec<String>) {
+        let mut ctx = DaeContext {
+            local_maps: document.local_maps(),
+            material_map: HashMap::new(),
+            coords: options.coords(document.asset.up_axis),
+        };
+
+        // materials without ids can't be referred to anyway
+        document.for_each(|material: &'a dae::Material| {
+            if let Some(id) = &material.id {
+                ctx.material_map.insert(id, TextureId(ctx.material_map.len() as u32));
+            }
+        });
+
+        let mut textures = vec![String::new(); ctx.material_map.len()];
+        for (tex, id) in &ctx.material_map {
+            textures[id.0 as usize] = tex.strip_suffix("-material").unwrap_or(tex).to_string();
         }
 
-        (vertices_out, normals_out, polygons_out)
+        (ctx, textures)
+    }
+
+    fn scene_nodes(&self, document: &dae::Document) -> Result<&'a [dae::Node], ImportError> {
+        let scene = &document
+            .scene
+            .as_ref()
+            .and_then(|scene| scene.instance_visual_scene.as_ref())
+            .ok_or(ImportError::NoScene)?
+            .url;
+        match self.local_maps.get(scene) {
+            Some(scene) => Ok(&scene.nodes),
+            None => Err(ImportError::MissingReference(format!("the scene {}", scene))),
+        }
     }
 }
 
-pub fn parse_dae(path: std::path::PathBuf) -> Box<Model> {
-    let document = dae::Document::from_file(&path).unwrap();
+// the options say how the file's axes and units map to pof's, with the up axis defaulting to the one the file specifies
+pub fn parse_dae(path: std::path::PathBuf, options: &ImportOptions) -> Result<(Model, Vec<ImportWarning>), ImportError> {
+    let document = dae::Document::from_file(&path)?;
     // use std::io::Write;
     // write!(std::fs::File::create("output.log").unwrap(), "{:#?}", document).unwrap();
-    let mut ctx = DaeContext {
-        local_maps: document.local_maps(),
-        material_map: HashMap::new(),
-        up: document.asset.up_axis,
-    };
-
-    document.for_each(|material: &dae::Material| {
-        ctx.material_map
-            .insert(material.id.as_ref().unwrap(), TextureId(ctx.material_map.len() as u32));
-    });
+    let (ctx, textures) = DaeContext::new(&document, options);
 
-    let mut model = Box::new(Model::default());
+    let mut model = Model::default();
     model.path_to_file = path.canonicalize().unwrap_or(path);
-    model.textures = vec![String::new(); ctx.material_map.len()];
-    for (tex, id) in &ctx.material_map {
-        model.textures[id.0 as usize] = tex.strip_suffix("-material").unwrap_or(tex).to_string();
-    }
+    model.textures = textures;
 
-    let scene = &document.scene.as_ref().unwrap().instance_visual_scene.as_ref().unwrap().url;
-    ctx.parse_top_level_nodes(&mut model, &ctx.local_maps.get(scene).unwrap().nodes);
-    model
+    let mut warnings = vec![];
+    ctx.parse_top_level_nodes(&mut model, ctx.scene_nodes(&document)?, &mut warnings)?;
+    Ok((model, warnings))
 }
 
 // =================================================================
@@ -1466,29 +1849,50 @@ impl<'a> IsNode<'a> for gltf::Node<'a> {
     fn transform(&self) -> Mat4x4 {
         self.transform().matrix().into()
     }
+    fn extra<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        let extras = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(self.extras().as_ref()?.get()).ok()?;
+        match extras.get(key)? {
+            serde_json::Value::String(val) => val.parse().ok(),
+            val => val.to_string().parse().ok(),
+        }
+    }
 }
 
 struct GltfContext {
     buffers: Vec<gltf::buffer::Data>,
+    coords: CoordSystem,
 }
 
 impl<'a> ParseCtx<'a> for GltfContext {
     type Node = gltf::Node<'a>;
-    fn parse_geometry(&self, node: &Self::Node, transform: &Mat4x4) -> (Vec<Vec3d>, Vec<Vec3d>, Vec<(TextureId, Vec<PolyVertex>)>) {
+    fn coords(&self) -> CoordSystem {
+        self.coords
+    }
+    fn parse_geometry(&self, node: &Self::Node, transform: &Mat4x4, warnings: &mut Vec<ImportWarning>) -> Result<ParsedGeometry, ImportError> {
         let mut vertices_out: Vec<Vec3d> = vec![];
         let mut vertices_map: HashMap<Vec3d, VertexId> = HashMap::new();
         let mut normals_out: Vec<Vec3d> = vec![];
         let mut normals_map: HashMap<Vec3d, NormalId> = HashMap::new();
         let mut polygons_out = vec![];
+        let mut extras = VertexExtras::default();
+        let node_name = node.name().unwrap_or_default();
 
         if let Some(mesh) = node.mesh() {
             for primitive in mesh.primitives() {
                 let reader = primitive.reader(|b| Some(&self.buffers[b.index()]));
+                let positions = match (primitive.mode(), reader.read_positions()) {
+                    (gltf::mesh::Mode::Triangles, Some(positions)) => positions,
+                    _ => {
+                        warnings.push(ImportWarning::UnsupportedGeometry(node_name.to_string()));
+                        continue;
+                    }
+                };
+
                 let mut vertex_ids = vec![];
-                for position in reader.read_positions().unwrap().map(Vec3d::from) {
+                for position in positions.map(Vec3d::from) {
                     vertex_ids.push(*vertices_map.entry(position).or_insert_with(|| {
                         let id = VertexId(vertices_out.len().try_into().unwrap());
-                        vertices_out.push((transform * position).from_coord(UpAxis::YUp));
+                        vertices_out.push((transform * position).from_coord(self.coords));
                         id
                     }));
                 }
@@ -1499,107 +1903,747 @@ impl<'a> ParseCtx<'a> for GltfContext {
                 let uvs = reader
                     .read_tex_coords(0)
                     .map(|iter| iter.into_f32().map(|[u, v]| (u, v)).collect::<Vec<_>>());
+                let extra_uvs = (1..)
+                    .map_while(|set| reader.read_tex_coords(set))
+                    .map(|iter| iter.into_f32().map(|[u, v]| (u, v)).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                if extras.uv_sets.len() < extra_uvs.len() {
+                    extras.uv_sets.resize_with(extra_uvs.len(), Default::default);
+                }
+                let colors = reader.read_colors(0).map(|iter| iter.into_rgba_f32().collect::<Vec<_>>());
+
+                let mut normal_ids = vec![];
+                if let Some(normal_iter) = reader.read_normals() {
+                    for normal in normal_iter.map(Vec3d::from) {
+                        normal_ids.push(*normals_map.entry(normal).or_insert_with(|| {
+                            let id = NormalId(normals_out.len().try_into().unwrap());
+                            normals_out.push((transform * normal).from_coord_dir(self.coords));
+                            id
+                        }));
+                    }
+                }
 
-                match primitive.mode() {
-                    gltf::mesh::Mode::Triangles => {
-                        let mut normal_ids = vec![];
-                        if let Some(normal_iter) = reader.read_normals() {
-                            for normal in normal_iter.map(Vec3d::from) {
-                                normal_ids.push(*normals_map.entry(normal).or_insert_with(|| {
-                                    let id = NormalId(normals_out.len().try_into().unwrap());
-                                    normals_out.push((transform * normal).from_coord(UpAxis::YUp));
-                                    id
-                                }));
-                            }
-                        }
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+                    None => (0..vertex_ids.len() as u32).collect(),
+                };
+                if indices.iter().any(|&i| i as usize >= vertex_ids.len()) {
+                    return Err(ImportError::InvalidData(format!("a mesh of {} has out of range indices", node_name)));
+                }
 
-                        macro_rules! on_indices {
-                            ($indices:expr) => {{
-                                let mut iter = $indices.map(|i| PolyVertex {
-                                    vertex_id: vertex_ids[i as usize],
-                                    normal_id: normal_ids.get(i as usize).copied().unwrap_or_default(),
-                                    uv: uvs.as_ref().map_or((0., 0.), |vec| vec[i as usize]),
-                                });
-                                while let Some(vert1) = iter.next() {
-                                    polygons_out.push((texture, vec![vert1, iter.next().unwrap(), iter.next().unwrap()]));
+                for tri in indices.chunks_exact(3) {
+                    let verts: Vec<_> = tri
+                        .iter()
+                        .map(|&i| {
+                            let polyvert = PolyVertex {
+                                vertex_id: vertex_ids[i as usize],
+                                normal_id: normal_ids.get(i as usize).copied().unwrap_or_default(),
+                                uv: uvs.as_ref().and_then(|vec| vec.get(i as usize)).copied().unwrap_or((0., 0.)),
+                            };
+                            for (set, uvs) in extras.uv_sets.iter_mut().zip(&extra_uvs) {
+                                if let Some(&uv) = uvs.get(i as usize) {
+                                    set.insert(polyvert.key(), uv);
                                 }
-                            }};
+                            }
+                            if let Some(&color) = colors.as_ref().and_then(|vec| vec.get(i as usize)) {
+                                extras.colors.insert(polyvert.key(), color);
+                            }
+                            polyvert
+                        })
+                        .collect();
+                    polygons_out.push((texture, verts));
+                }
+            }
+        }
+
+        if !self.coords.flip_winding() {
+            for poly in &mut polygons_out {
+                poly.1.reverse(); // normal facing (which is determined by winding order) is inverted for FSO
+            }
+        }
+
+        let extras = if extras.is_empty() { None } else { Some(extras) };
+        Ok((vertices_out, normals_out, polygons_out, extras))
+    }
+}
+
+impl GltfContext {
+    // also returns the document itself and the texture names, indexed by the texture ids the context will produce
+    fn load(
+        path: &std::path::Path, options: &ImportOptions, warnings: &mut Vec<ImportWarning>,
+    ) -> Result<(Self, gltf::Document, Vec<String>), ImportError> {
+        let base = path.parent().unwrap_or_else(|| std::path::Path::new("./"));
+        let reader = BufReader::new(File::open(path)?);
+        let gltf::Gltf { document: gltf, blob } = gltf::Gltf::from_reader(reader)?;
+        // let buffers = gltf::import::import_buffer_data(&gltf, Some(base), blob).unwrap();
+        let buffers = import_buffer_data(&gltf, base, blob)?;
+        let textures = gltf
+            .materials()
+            .enumerate()
+            .map(|(i, mat)| match mat.name() {
+                Some(name) => name.strip_suffix("-material").unwrap_or(name).to_string(),
+                None => {
+                    warnings.push(ImportWarning::UnnamedMaterial(i));
+                    format!("material{}", i)
+                }
+            })
+            .collect();
+
+        // glTF is always y up, though not every program follows that
+        Ok((GltfContext { buffers, coords: options.coords(UpAxis::YUp) }, gltf, textures))
+    }
+}
+
+// the default scene, or if there isn't one, the first
+fn gltf_scene(gltf: &gltf::Document) -> Result<gltf::Scene<'_>, ImportError> {
+    gltf.default_scene().or_else(|| gltf.scenes().next()).ok_or(ImportError::NoScene)
+}
+
+pub fn parse_gltf(path: std::path::PathBuf, options: &ImportOptions) -> Result<(Model, Vec<ImportWarning>), ImportError> {
+    let mut warnings = vec![];
+    let (ctx, gltf, textures) = GltfContext::load(&path, options, &mut warnings)?;
+    let mut model = Model::default();
+    model.path_to_file = path.canonicalize().unwrap_or(path);
+    model.textures = textures;
+
+    ctx.parse_top_level_nodes(&mut model, gltf_scene(&gltf)?.nodes(), &mut warnings)?;
+    Ok((model, warnings))
+}
+
+// =================================================================
+// OBJ parsing
+// =================================================================
+
+// the extension of the file next to an OBJ file which holds everything it can't, see Model::write_obj_metadata
+pub const OBJ_METADATA_EXTENSION: &str = "pofmeta";
+
+// an object (or group) of an OBJ file, positioned in model space
+struct ObjObject {
+    name: String,
+    verts: Vec<Vec3d>,
+    norms: Vec<Vec3d>,
+    polygons: Vec<(TextureId, Vec<PolyVertex>)>,
+    missing_normals: bool,
+    // where the file's vertices and normals ended up in this object
+    vert_map: HashMap<usize, VertexId>,
+    norm_map: HashMap<usize, NormalId>,
+}
+
+// reads the objects of an OBJ file, and the names of the materials they use
+fn read_obj_objects(path: &std::path::Path, options: &ImportOptions) -> Result<(Vec<ObjObject>, Vec<String>), ImportError> {
+    // indices are 1-based, or if negative, count back from the end
+    fn resolve(idx: &str, len: usize) -> Option<usize> {
+        let idx = idx.parse::<isize>().ok()?;
+        let idx = if idx < 0 { len as isize + idx } else { idx - 1 };
+        (0..len as isize).contains(&idx).then(|| idx as usize)
+    }
+
+    let text = std::fs::read_to_string(path)?;
+    let coords = options.coords(UpAxis::YUp);
+
+    // groups are only used as objects if there aren't any actual objects
+    let use_groups = !text.lines().any(|line| line.trim_start().starts_with("o "));
+
+    let (mut positions, mut uvs, mut normals) = (vec![], vec![], vec![]);
+    let mut textures: Vec<String> = vec![];
+    let mut texture = TextureId::UNTEXTURED;
+    let mut objects: Vec<ObjObject> = vec![];
+    let mut current = None;
+
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let bad_line = || ImportError::InvalidData(format!("line {} of {}: {}", line_num + 1, path.display(), line));
+        let floats = || {
+            rest.split_whitespace()
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| bad_line())
+        };
+        match keyword {
+            "v" => match *floats()? {
+                [x, y, z, ..] => positions.push(Vec3d::new(x, y, z).from_coord(coords)),
+                _ => return Err(bad_line()),
+            },
+            "vn" => match *floats()? {
+                [x, y, z, ..] => normals.push(Vec3d::new(x, y, z).from_coord_dir(coords)),
+                _ => return Err(bad_line()),
+            },
+            "vt" => match *floats()? {
+                [u, v, ..] => uvs.push((u, 1. - v)),
+                [u] => uvs.push((u, 1.)),
+                _ => return Err(bad_line()),
+            },
+            "o" | "g" if keyword == "o" || use_groups => {
+                current = Some(match objects.iter().position(|obj| obj.name == rest) {
+                    Some(idx) => idx,
+                    None => {
+                        objects.push(ObjObject {
+                            name: rest.to_string(),
+                            verts: vec![],
+                            norms: vec![],
+                            polygons: vec![],
+                            missing_normals: false,
+                            vert_map: HashMap::new(),
+                            norm_map: HashMap::new(),
+                        });
+                        objects.len() - 1
+                    }
+                });
+            }
+            "usemtl" => {
+                texture = match textures.iter().position(|tex| tex == rest) {
+                    Some(idx) => TextureId(idx as u32),
+                    None => {
+                        textures.push(rest.to_string());
+                        TextureId(textures.len() as u32 - 1)
+                    }
+                };
+            }
+            "f" => {
+                // geometry outside of any object is named after the file
+                let obj = match current {
+                    Some(idx) => &mut objects[idx],
+                    None => {
+                        let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+                        objects.push(ObjObject {
+                            name,
+                            verts: vec![],
+                            norms: vec![],
+                            polygons: vec![],
+                            missing_normals: false,
+                            vert_map: HashMap::new(),
+                            norm_map: HashMap::new(),
+                        });
+                        current = Some(objects.len() - 1);
+                        objects.last_mut().unwrap()
+                    }
+                };
+
+                let mut verts = vec![];
+                for vert in rest.split_whitespace() {
+                    let mut indices = vert.split('/');
+                    let vert_idx = indices.next().and_then(|idx| resolve(idx, positions.len())).ok_or_else(bad_line)?;
+                    let uv = match indices.next() {
+                        None | Some("") => (0., 0.),
+                        Some(idx) => uvs[resolve(idx, uvs.len()).ok_or_else(bad_line)?],
+                    };
+                    let normal_id = match indices.next() {
+                        None | Some("") => {
+                            obj.missing_normals = true;
+                            NormalId(0)
                         }
-                        match reader.read_indices() {
-                            Some(indices) => on_indices!(indices.into_u32()),
-                            None => on_indices!(0..vertex_ids.len() as u32),
+                        Some(idx) => {
+                            let norm_idx = resolve(idx, normals.len()).ok_or_else(bad_line)?;
+                            *obj.norm_map.entry(norm_idx).or_insert_with(|| {
+                                obj.norms.push(normals[norm_idx]);
+                                NormalId(obj.norms.len() as u32 - 1)
+                            })
                         }
+                    };
+                    let vertex_id = *obj.vert_map.entry(vert_idx).or_insert_with(|| {
+                        obj.verts.push(positions[vert_idx]);
+                        VertexId(obj.verts.len() as u32 - 1)
+                    });
+                    verts.push(PolyVertex { vertex_id, normal_id, uv });
+                }
+
+                if verts.len() >= 3 {
+                    // normal facing inverted for FSO
+                    if !coords.flip_winding() {
+                        verts.reverse();
                     }
-                    _ => unreachable!(),
+                    obj.polygons.push((texture, verts));
                 }
             }
+            // comments, materials files, smoothing groups, lines etc are of no use
+            _ => {}
+        }
+    }
+
+    Ok((objects, textures))
+}
+
+// the smoothing used for OBJ objects without normals, which are quite common
+const OBJ_SMOOTHING_ANGLE: f32 = 30.0;
+
+// the options are applied to the geometry only, the metadata file is in pof coordinates regardless
+pub fn parse_obj(path: PathBuf, options: &ImportOptions) -> Result<(Model, Vec<ImportWarning>), ImportError> {
+    let mut warnings = vec![];
+    let (objects, textures) = read_obj_objects(&path, options)?;
+    let mut model = Model { textures, ..Default::default() };
+
+    let metadata_path = path.with_extension(OBJ_METADATA_EXTENSION);
+    let metadata = if metadata_path.exists() {
+        Some(std::fs::read_to_string(&metadata_path)?)
+    } else {
+        None
+    };
+    // objects with no polygons are only kept if the metadata has them as subobjects, like helpers written by write_obj
+    let metadata_subobjs = metadata
+        .iter()
+        .flat_map(|text| text.lines())
+        .filter_map(|line| line.trim_end().strip_prefix("subobject "))
+        .collect::<HashSet<_>>();
+
+    // OBJ has no hierarchy, everything is in model space until the subobjects are placed below
+    let mut insignia_names = vec![];
+    for obj in objects {
+        if obj.polygons.is_empty() && !metadata_subobjs.contains(obj.name.as_str()) {
+            continue;
+        }
+        let name = obj.name.to_lowercase();
+        if name == "shield" {
+            model.shield_data = Some(mk_shield(obj.verts, obj.polygons));
+        } else if name.contains("insig") {
+            insignia_names.push(obj.name);
+            model.insignias.push(mk_insignia(None, Vec3d::ZERO, obj.verts, obj.polygons));
+        } else {
+            let is_debris = obj.name.starts_with("debris");
+            let obj_id = push_subobj(&mut model.sub_objects, Vec3d::ZERO, None, &obj.name, is_debris, obj.verts, obj.norms, obj.polygons, None);
+            if obj.missing_normals {
+                model.sub_objects[obj_id].recalc_normals(OBJ_SMOOTHING_ANGLE.to_radians(), true);
+                warnings.push(ImportWarning::NormalsCalculated(obj.name.clone()));
+            }
+            register_detail_level(&mut model, &obj.name, obj_id);
         }
+    }
 
-        for poly in &mut polygons_out {
-            poly.1.reverse(); // normal facing (which is determined by winding order) is inverted for FSO
+    let placed = match &metadata {
+        Some(metadata) => read_obj_metadata(&mut model, metadata, &insignia_names, &mut warnings),
+        None => HashSet::new(),
+    };
+
+    // anything the metadata didn't place, other than detail levels and debris, goes under detail0, centered on its geometry
+    if let Some(&detail0) = model.header.detail_levels.first() {
+        for i in 0..model.sub_objects.len() {
+            let id = ObjectId(i as u32);
+            let subobj = &model.sub_objects[id];
+            if placed.contains(&id) || subobj.is_debris_model || model.header.detail_levels.contains(&id) || model.is_obj_id_ancestor(detail0, id) {
+                continue;
+            }
+            let offset = Vec3d::average(subobj.bsp_data.verts.iter().copied()) - model.get_total_subobj_offset(detail0);
+            let subobj = &mut model.sub_objects[id];
+            subobj.parent = Some(detail0);
+            subobj.offset = offset;
+            subobj.geo_center = offset;
         }
+    }
 
-        (vertices_out, normals_out, polygons_out)
+    for i in 0..model.sub_objects.len() {
+        let id = ObjectId(i as u32);
+        let offset = model.get_total_subobj_offset(id);
+        let subobj = &mut model.sub_objects[id];
+        for vert in &mut subobj.bsp_data.verts {
+            *vert -= offset;
+        }
+        subobj.bsp_data.collision_tree.recalculate_bboxes(&subobj.bsp_data.verts);
+        subobj.recalc_bbox();
+        subobj.recalc_radius();
     }
-}
+    for insignia in &mut model.insignias {
+        for vert in &mut insignia.vertices {
+            *vert -= insignia.offset;
+        }
+    }
+
+    finish_import(&mut model);
 
-pub fn parse_gltf(path: std::path::PathBuf) -> Box<Model> {
-    let base = path.parent().unwrap_or_else(|| std::path::Path::new("./"));
-    let reader = BufReader::new(File::open(&path).unwrap());
-    let gltf::Gltf { document: gltf, blob } = gltf::Gltf::from_reader(reader).unwrap();
-    // let buffers = gltf::import::import_buffer_data(&gltf, Some(base), blob).unwrap();
-    let buffers = import_buffer_data(&gltf, &base, blob);
-    let mut model = Box::new(Model::default());
     model.path_to_file = path.canonicalize().unwrap_or(path);
-    model.textures = gltf
-        .materials()
-        .map(|mat| mat.name().unwrap().strip_suffix("-material").unwrap_or(mat.name().unwrap()).to_string())
-        .collect();
 
-    GltfContext { buffers }.parse_top_level_nodes(&mut model, gltf.default_scene().unwrap().nodes());
-    model
+    Ok((model, warnings))
+}
+
+// undoes escape_metadata, for multi-line text which was put on one line
+fn unescape_metadata(val: &str) -> String {
+    let mut out = String::new();
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(c) => out.push(c),
+                None => {}
+            },
+            (c, false) => out.push(c),
+        }
+    }
+    out
+}
+
+// reads the metadata written by Model::write_obj_metadata into a model freshly imported from the OBJ
+// returns the subobjects it had entries for
+fn read_obj_metadata(model: &mut Model, text: &str, insignia_names: &[String], warnings: &mut Vec<ImportWarning>) -> HashSet<ObjectId> {
+    // the entry which the lines below it apply to
+    #[derive(Clone, Copy)]
+    enum Entry {
+        None,
+        Skipped,
+        Subobject(ObjectId),
+        Insignia(usize),
+        Turret(usize),
+        ThrusterBank(usize),
+        WeaponBank(bool, usize),
+        Dock(usize),
+        GlowBank(usize),
+        SpecialPoint(usize),
+        EyePoint(usize),
+        Path(usize),
+    }
+
+    fn subobj_id(model: &Model, name: &str) -> Result<ObjectId, ImportWarning> {
+        model
+            .get_obj_id_by_name(name)
+            .ok_or_else(|| ImportWarning::UnknownSubobject(name.to_string()))
+    }
+
+    let mut placed = HashSet::new();
+    let mut entry = Entry::None;
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let invalid = || ImportWarning::InvalidMetadata(line_num + 1, line.to_string());
+        let vec = || -> Result<Vec3d, ImportWarning> {
+            match *rest
+                .split(' ')
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?
+            {
+                [x, y, z] => Ok(Vec3d::new(x, y, z)),
+                _ => Err(invalid()),
+            }
+        };
+        // position, normal and radius
+        let point = || -> Result<(Vec3d, Vec3d, f32), ImportWarning> {
+            match *rest
+                .split(' ')
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?
+            {
+                [x, y, z, nx, ny, nz, radius] => Ok((Vec3d::new(x, y, z), Vec3d::new(nx, ny, nz), radius)),
+                _ => Err(invalid()),
+            }
+        };
+
+        let mut result = || -> Result<(), ImportWarning> {
+            match (keyword, entry) {
+                ("subobject", _) => {
+                    entry = Entry::Skipped;
+                    let id = subobj_id(model, rest)?;
+                    placed.insert(id);
+                    entry = Entry::Subobject(id);
+                }
+                ("parent", Entry::Subobject(id)) => {
+                    let parent = subobj_id(model, rest)?;
+                    if model.is_obj_id_ancestor(parent, id) {
+                        return Err(invalid());
+                    }
+                    model.sub_objects[id].parent = Some(parent);
+                }
+                ("offset", Entry::Subobject(id)) => {
+                    let offset = vec()?;
+                    model.sub_objects[id].offset = offset;
+                    model.sub_objects[id].geo_center = offset;
+                }
+                ("movement", Entry::Subobject(id)) => {
+                    let (movement_type, movement_axis) = rest.split_once(' ').ok_or_else(invalid)?;
+                    let movement_type = movement_type.parse::<i32>().ok().and_then(|val| SubsysMovementType::try_from(val).ok());
+                    let movement_axis = movement_axis.parse::<i32>().ok().and_then(|val| SubsysMovementAxis::try_from(val).ok());
+                    let subobj = &mut model.sub_objects[id];
+                    subobj.movement_type = movement_type.ok_or_else(invalid)?;
+                    subobj.movement_axis = movement_axis.ok_or_else(invalid)?;
+                }
+                ("debris", Entry::Subobject(id)) => model.sub_objects[id].is_debris_model = true,
+                ("properties", _) => {
+                    let properties = match entry {
+                        Entry::Subobject(id) => &mut model.sub_objects[id].properties,
+                        Entry::ThrusterBank(idx) => &mut model.thruster_banks[idx].properties,
+                        Entry::Dock(idx) => &mut model.docking_bays[idx].properties,
+                        Entry::GlowBank(idx) => &mut model.glow_banks[idx].properties,
+                        Entry::SpecialPoint(idx) => &mut model.special_points[idx].properties,
+                        Entry::Skipped => return Ok(()),
+                        _ => return Err(invalid()),
+                    };
+                    *properties = unescape_metadata(rest);
+                }
+                ("insignia", _) => {
+                    entry = Entry::Skipped;
+                    let idx = insignia_names
+                        .iter()
+                        .position(|name| name == rest)
+                        .ok_or_else(|| ImportWarning::UnknownSubobject(rest.to_string()))?;
+                    entry = Entry::Insignia(idx);
+                }
+                ("detail_level", Entry::Insignia(idx)) => model.insignias[idx].detail_level = rest.parse().map_err(|_| invalid())?,
+                ("offset", Entry::Insignia(idx)) => model.insignias[idx].offset = vec()?,
+                ("turret", _) => {
+                    entry = Entry::Skipped;
+                    let base_obj = subobj_id(model, rest)?;
+                    model.turrets.push(Turret { base_obj, gun_obj: base_obj, ..Default::default() });
+                    entry = Entry::Turret(model.turrets.len() - 1);
+                }
+                ("gun", Entry::Turret(idx)) => model.turrets[idx].gun_obj = subobj_id(model, rest)?,
+                ("normal", Entry::Turret(idx)) => model.turrets[idx].normal = vec()?.try_into().unwrap_or_default(),
+                ("point", Entry::Turret(idx)) => model.turrets[idx].fire_points.push(vec()?),
+                ("thruster_bank", _) => {
+                    model.thruster_banks.push(Default::default());
+                    entry = Entry::ThrusterBank(model.thruster_banks.len() - 1);
+                }
+                ("glow", Entry::ThrusterBank(idx)) => {
+                    let (position, normal, radius) = point()?;
+                    model.thruster_banks[idx].glows.push(ThrusterGlow { position, normal, radius });
+                }
+                ("primary_bank" | "secondary_bank", _) => {
+                    let is_primary = keyword == "primary_bank";
+                    let banks = if is_primary {
+                        &mut model.primary_weps
+                    } else {
+                        &mut model.secondary_weps
+                    };
+                    banks.push(vec![]);
+                    entry = Entry::WeaponBank(is_primary, banks.len() - 1);
+                }
+                ("hardpoint", Entry::WeaponBank(is_primary, idx)) => {
+                    let (position, normal, offset) = point()?;
+                    let banks = if is_primary {
+                        &mut model.primary_weps
+                    } else {
+                        &mut model.secondary_weps
+                    };
+                    banks[idx].push(WeaponHardpoint {
+                        position,
+                        normal: normal.try_into().unwrap_or_default(),
+                        offset,
+                    });
+                }
+                ("dock", _) => {
+                    model.docking_bays.push(Default::default());
+                    entry = Entry::Dock(model.docking_bays.len() - 1);
+                }
+                ("position", Entry::Dock(idx)) => model.docking_bays[idx].position = vec()?,
+                ("fvec", Entry::Dock(idx)) => model.docking_bays[idx].fvec = vec()?.try_into().unwrap_or_default(),
+                ("uvec", Entry::Dock(idx)) => model.docking_bays[idx].uvec = vec()?.try_into().unwrap_or_default(),
+                ("dock_path", Entry::Dock(idx)) => model.docking_bays[idx].path = Some(PathId(rest.parse().map_err(|_| invalid())?)),
+                ("glow_bank", _) => {
+                    entry = Entry::Skipped;
+                    let obj_parent = subobj_id(model, rest)?;
+                    model.glow_banks.push(GlowPointBank { obj_parent, ..Default::default() });
+                    entry = Entry::GlowBank(model.glow_banks.len() - 1);
+                }
+                ("timing", Entry::GlowBank(idx)) => match *rest.split(' ').collect::<Vec<_>>() {
+                    [disp_time, on_time, off_time] => {
+                        let bank = &mut model.glow_banks[idx];
+                        bank.disp_time = disp_time.parse().map_err(|_| invalid())?;
+                        bank.on_time = on_time.parse().map_err(|_| invalid())?;
+                        bank.off_time = off_time.parse().map_err(|_| invalid())?;
+                    }
+                    _ => return Err(invalid()),
+                },
+                ("lod", Entry::GlowBank(idx)) => model.glow_banks[idx].lod = rest.parse().map_err(|_| invalid())?,
+                ("type", Entry::GlowBank(idx)) => model.glow_banks[idx].glow_type = rest.parse().map_err(|_| invalid())?,
+                ("glow", Entry::GlowBank(idx)) => {
+                    let (position, normal, radius) = point()?;
+                    model.glow_banks[idx].glow_points.push(GlowPoint { position, normal, radius });
+                }
+                ("special_point", _) => {
+                    model.special_points.push(SpecialPoint { name: rest.to_string(), ..Default::default() });
+                    entry = Entry::SpecialPoint(model.special_points.len() - 1);
+                }
+                ("position", Entry::SpecialPoint(idx)) => model.special_points[idx].position = vec()?,
+                ("radius", Entry::SpecialPoint(idx)) => model.special_points[idx].radius = rest.parse().map_err(|_| invalid())?,
+                ("eye_point", _) => {
+                    entry = Entry::Skipped;
+                    let attached_subobj = subobj_id(model, rest)?;
+                    model.eye_points.push(EyePoint { attached_subobj, ..Default::default() });
+                    entry = Entry::EyePoint(model.eye_points.len() - 1);
+                }
+                ("position", Entry::EyePoint(idx)) => model.eye_points[idx].offset = vec()?,
+                ("normal", Entry::EyePoint(idx)) => model.eye_points[idx].normal = vec()?.try_into().unwrap_or_default(),
+                ("path", _) => {
+                    model.paths.push(Path { name: rest.to_string(), ..Default::default() });
+                    entry = Entry::Path(model.paths.len() - 1);
+                }
+                ("parent", Entry::Path(idx)) => model.paths[idx].parent = rest.to_string(),
+                ("point", Entry::Path(idx)) => match *rest
+                    .split(' ')
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid())?
+                {
+                    [x, y, z, radius] => model.paths[idx]
+                        .points
+                        .push(PathPoint { position: Vec3d::new(x, y, z), radius, turrets: vec![] }),
+                    _ => return Err(invalid()),
+                },
+                ("point_turret", Entry::Path(idx)) => {
+                    let turret = subobj_id(model, rest)?;
+                    model.paths[idx].points.last_mut().ok_or_else(invalid)?.turrets.push(turret);
+                }
+                ("visual_center", _) => model.visual_center = vec()?,
+                ("comments", _) => model.comments = unescape_metadata(rest),
+                // the lines of entries which couldn't be read
+                (_, Entry::Skipped) => {}
+                _ => return Err(invalid()),
+            }
+            Ok(())
+        };
+        if let Err(warning) = result() {
+            warnings.push(warning);
+        }
+    }
+
+    // paths are written after docks, so these can only be checked at the end
+    for dock in &mut model.docking_bays {
+        if matches!(dock.path, Some(path) if path.0 as usize >= model.paths.len()) {
+            dock.path = None;
+        }
+    }
+
+    placed
+}
+
+// =================================================================
+// Single subobject import
+// =================================================================
+
+impl Model {
+    // replaces a subobject's geometry with that of the node with the given name in a DAE, glTF or OBJ file
+    // everything else about the subobject (name, properties, children, hardpoints etc) is kept as is
+    // the geometry is taken relative to the node's own origin, which ends up at the subobject's offset
+    pub fn replace_subobject_geometry(
+        &mut self, id: ObjectId, path: PathBuf, node_name: &str, options: &ImportOptions,
+    ) -> Result<Vec<ImportWarning>, ImportError> {
+        let not_found = || ImportError::NodeNotFound(node_name.to_string());
+        let mut warnings = vec![];
+
+        let ext = path.extension().map(|ext| ext.to_ascii_lowercase());
+        let mut missing_normals = false;
+        let ((verts, norms, polygons, extras), textures) = match ext.as_ref().and_then(|ext| ext.to_str()) {
+            Some("dae") => {
+                let document = dae::Document::from_file(&path)?;
+                let (ctx, textures) = DaeContext::new(&document, options);
+                let geometry = ctx.parse_named_node_geometry(ctx.scene_nodes(&document)?, node_name, &Mat4x4::identity(), &mut warnings)?;
+                (geometry.ok_or_else(not_found)?, textures)
+            }
+            Some("gltf" | "glb") => {
+                let (ctx, gltf, textures) = GltfContext::load(&path, options, &mut warnings)?;
+                let geometry = ctx.parse_named_node_geometry(gltf_scene(&gltf)?.nodes(), node_name, &Mat4x4::identity(), &mut warnings)?;
+                (geometry.ok_or_else(not_found)?, textures)
+            }
+            Some("obj") => {
+                let (objects, textures) = read_obj_objects(&path, options)?;
+                let obj = objects.into_iter().find(|obj| obj.name == node_name).ok_or_else(not_found)?;
+                if obj.missing_normals {
+                    warnings.push(ImportWarning::NormalsCalculated(obj.name.clone()));
+                    missing_normals = true;
+                }
+                // OBJ has no origins, its positions are in model space
+                let offset = self.get_total_subobj_offset(id);
+                let verts = obj.verts.into_iter().map(|vert| vert - offset).collect();
+                ((verts, obj.norms, obj.polygons, None), textures)
+            }
+            _ => return Err(ImportError::UnsupportedFileType(path)),
+        };
+
+        if polygons.is_empty() || verts.is_empty() || (norms.is_empty() && !missing_normals) {
+            return Err(not_found());
+        }
+
+        let untextured = polygons.iter().any(|(texture, _)| textures.get(texture.0 as usize).is_none());
+
+        // the file's textures are matched up by name with the model's, adding any which are new
+        let mut texture_map = HashMap::new();
+        let polygons = polygons.into_iter().map(|(texture, verts)| {
+            let texture = match textures.get(texture.0 as usize) {
+                Some(name) => *texture_map
+                    .entry(texture)
+                    .or_insert_with(|| match self.textures.iter().position(|tex| tex == name) {
+                        Some(idx) => TextureId(idx as u32),
+                        None => {
+                            self.textures.push(name.clone());
+                            TextureId(self.textures.len() as u32 - 1)
+                        }
+                    }),
+                None => TextureId::UNTEXTURED,
+            };
+            Polygon { normal: Default::default(), texture, verts }
+        });
+
+        let subobj = &mut self.sub_objects[id];
+        subobj.bsp_data = BspData {
+            collision_tree: BspData::recalculate(&verts, polygons),
+            verts,
+            norms,
+            extras,
+        };
+        if missing_normals {
+            subobj.recalc_normals(OBJ_SMOOTHING_ANGLE.to_radians(), true);
+        }
+        subobj.recalc_bbox();
+        subobj.recalc_radius();
+
+        if untextured {
+            self.untextured_idx = post_parse_fill_untextured_slot(&mut self.sub_objects, &mut self.textures);
+        }
+
+        self.recalc_radius();
+        self.recalc_bbox();
+        Ok(warnings)
+    }
 }
 
 // Polyfill for https://github.com/gltf-rs/gltf/pull/341 - delete me
-fn import_buffer_data(document: &gltf::Document, base: &std::path::Path, mut blob: Option<Vec<u8>>) -> Vec<gltf::buffer::Data> {
-    fn read_to_end(path: impl AsRef<std::path::Path>) -> Vec<u8> {
-        let file = File::open(path.as_ref()).unwrap();
+fn import_buffer_data(document: &gltf::Document, base: &std::path::Path, mut blob: Option<Vec<u8>>) -> Result<Vec<gltf::buffer::Data>, ImportError> {
+    fn read_to_end(path: impl AsRef<std::path::Path>) -> io::Result<Vec<u8>> {
+        let file = File::open(path.as_ref())?;
         let length = file.metadata().map(|x| x.len() + 1).unwrap_or(0);
         let mut reader = io::BufReader::new(file);
         let mut data = Vec::with_capacity(length as usize);
-        reader.read_to_end(&mut data).unwrap();
-        data
+        reader.read_to_end(&mut data)?;
+        Ok(data)
     }
 
     let mut buffers = Vec::new();
     for buffer in document.buffers() {
+        let bad_buffer = |what: String| ImportError::BadBuffer(buffer.index(), what);
         let mut data = match buffer.source() {
             gltf::buffer::Source::Uri(uri) => {
                 if uri.contains(':') {
                     if let Some(rest) = uri.strip_prefix("data:") {
                         let mut it = rest.split(";base64,");
                         match (it.next(), it.next()) {
-                            (_, Some(base64)) | (Some(base64), _) => base64::decode(&base64).unwrap(),
-                            _ => panic!(),
+                            (_, Some(base64)) | (Some(base64), _) => {
+                                base64::decode(&base64).map_err(|err| bad_buffer(format!("invalid base64 data: {}", err)))?
+                            }
+                            _ => return Err(bad_buffer(format!("empty data uri"))),
                         }
                     } else if let Some(rest) = uri.strip_prefix("file://") {
-                        read_to_end(rest)
+                        read_to_end(rest)?
                     } else if let Some(rest) = uri.strip_prefix("file:") {
-                        read_to_end(rest)
+                        read_to_end(rest)?
                     } else {
-                        panic!()
+                        return Err(bad_buffer(format!("unsupported uri {}", uri)));
                     }
                 } else {
-                    read_to_end(base.join(&*urlencoding::decode(uri).unwrap()))
+                    let uri = urlencoding::decode(uri).map_err(|err| bad_buffer(format!("invalid uri {}: {}", uri, err)))?;
+                    read_to_end(base.join(&*uri))?
                 }
             }
-            gltf::buffer::Source::Bin => blob.take().unwrap(),
+            gltf::buffer::Source::Bin => blob.take().ok_or_else(|| bad_buffer(format!("missing binary chunk")))?,
         };
+        if data.len() < buffer.length() {
+            return Err(bad_buffer(format!("expected {} bytes, but only found {}", buffer.length(), data.len())));
+        }
         while data.len() % 4 != 0 {
             data.push(0);
         }
-        assert!(data.len() >= buffer.length());
         buffers.push(gltf::buffer::Data(data));
     }
-    buffers
+    Ok(buffers)
 }
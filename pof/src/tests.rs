@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::*;
+
+fn poly(verts: &[usize]) -> Polygon {
+    Polygon {
+        normal: Vec3d::ZERO,
+        texture: TextureId(0),
+        verts: verts
+            .iter()
+            .map(|&i| PolyVertex {
+                vertex_id: VertexId(i as u32),
+                normal_id: NormalId(0),
+                uv: (0.0, 0.0),
+            })
+            .collect(),
+    }
+}
+
+// a subobject from a list of positions and faces, with flat normals
+fn mesh_subobject(name: &str, verts: Vec<Vec3d>, faces: &[Vec<usize>]) -> SubObject {
+    let mut subobj = SubObject {
+        name: name.to_string(),
+        bsp_data: BspData {
+            collision_tree: BspData::recalculate(&verts, faces.iter().map(|face| poly(face))),
+            verts,
+            norms: vec![],
+            extras: None,
+        },
+        ..Default::default()
+    };
+    subobj.recalc_normals_flat();
+    subobj.recalc_bbox();
+    subobj.recalc_radius();
+    subobj
+}
+
+fn cube_verts(size: f32) -> Vec<Vec3d> {
+    (0..8)
+        .map(|i| Vec3d::new(if i & 1 == 0 { -size } else { size }, if i & 2 == 0 { -size } else { size }, if i & 4 == 0 { -size } else { size }))
+        .collect()
+}
+
+fn cube_subobject(name: &str, size: f32) -> SubObject {
+    let faces = [[0, 1, 3, 2], [4, 6, 7, 5], [0, 4, 5, 1], [2, 3, 7, 6], [0, 2, 6, 4], [1, 5, 7, 3]];
+    mesh_subobject(name, cube_verts(size), &faces.map(|face| face.to_vec()))
+}
+
+// a closed uv sphere, with single vertices at the poles
+fn sphere_subobject(name: &str, rings: usize, segments: usize) -> SubObject {
+    let mut verts = vec![Vec3d::new(0.0, 1.0, 0.0)];
+    for ring in 1..rings {
+        let (ring_radius, y) = (std::f32::consts::PI * ring as f32 / rings as f32).sin_cos();
+        for segment in 0..segments {
+            let (z, x) = (std::f32::consts::TAU * segment as f32 / segments as f32).sin_cos();
+            verts.push(Vec3d::new(x * ring_radius, y, z * ring_radius));
+        }
+    }
+    verts.push(Vec3d::new(0.0, -1.0, 0.0));
+
+    let ring_vert = |ring: usize, segment: usize| 1 + (ring - 1) * segments + segment % segments;
+    let bottom = verts.len() - 1;
+    let mut faces = vec![];
+    for segment in 0..segments {
+        faces.push(vec![0, ring_vert(1, segment + 1), ring_vert(1, segment)]);
+        for ring in 1..rings - 1 {
+            let (a, b) = (ring_vert(ring, segment), ring_vert(ring, segment + 1));
+            let (c, d) = (ring_vert(ring + 1, segment), ring_vert(ring + 1, segment + 1));
+            faces.push(vec![a, b, d]);
+            faces.push(vec![a, d, c]);
+        }
+        faces.push(vec![bottom, ring_vert(rings - 1, segment), ring_vert(rings - 1, segment + 1)]);
+    }
+    mesh_subobject(name, verts, &faces)
+}
+
+// detail0, with a turret (base and gun), a radar dish and an empty helper below it
+fn test_model() -> Model {
+    let mut model = Model { textures: vec!["hull".to_string()], ..Default::default() };
+    let detail0 = model.add_subobject(cube_subobject("detail0", 4.0), None);
+    model.header.detail_levels.push(detail0);
+
+    let mut base = cube_subobject("turret01a", 1.0);
+    base.offset = Vec3d::new(0.0, 5.0, 0.0);
+    base.properties = "$fov=120".to_string();
+    let base = model.add_subobject(base, Some(detail0));
+    let mut gun = cube_subobject("turret01b", 0.5);
+    gun.offset = Vec3d::new(0.0, 1.5, 0.0);
+    let gun = model.add_subobject(gun, Some(base));
+    model.turrets.push(Turret {
+        base_obj: base,
+        gun_obj: gun,
+        normal: NormalVec3(Vec3d::new(0.0, 1.0, 0.0)),
+        fire_points: vec![Vec3d::new(0.0, 0.5, 1.0)],
+    });
+
+    let mut radar = cube_subobject("radar", 0.5);
+    radar.offset = Vec3d::new(2.0, 4.5, -2.0);
+    radar.properties = "$rotate=5".to_string();
+    radar.movement_type = SubsysMovementType::Regular;
+    radar.movement_axis = SubsysMovementAxis::Y;
+    model.add_subobject(radar, Some(detail0));
+
+    let mut helper = SubObject {
+        name: "helper".to_string(),
+        offset: Vec3d::new(-3.0, 0.0, 3.0),
+        ..Default::default()
+    };
+    helper.properties = "$special=subsystem".to_string();
+    model.add_subobject(helper, Some(detail0));
+
+    model
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pof-tools-test-{}-{}", std::process::id(), name))
+}
+
+fn num_polygons(subobj: &SubObject) -> usize {
+    subobj.bsp_data.collision_tree.leaves().count()
+}
+
+fn assert_vec_eq(a: Vec3d, b: Vec3d) {
+    assert!((a - b).magnitude() < 0.0001, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn triangulate_concave() {
+    // an L shape, whose notch is at (1..2, 1..2)
+    let verts = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)].map(|(x, y)| Vec3d::new(x, y, 0.0));
+    let polygon = poly(&[0, 1, 2, 3, 4, 5]);
+    let tris = polygon.triangulate(&verts);
+
+    assert_eq!(tris.len(), 4);
+    let mut area = 0.0;
+    for tri in &tris {
+        let [a, b, c] = [0, 1, 2].map(|i| verts[tri.verts[i].vertex_id.0 as usize]);
+        // wound the same way as the polygon, and none of them covering the notch
+        let cross = (b - a).cross(c - a);
+        assert!(cross.z > 0.0);
+        area += cross.z / 2.0;
+        let center = (a + b + c) / 3.0;
+        assert!(!(center.x > 1.0 && center.y > 1.0));
+    }
+    assert!((area - 3.0).abs() < 0.0001);
+}
+
+#[test]
+fn convex_hull_cube() {
+    let mut points = cube_verts(1.0);
+    // points inside shouldn't end up on the hull
+    points.push(Vec3d::ZERO);
+    points.push(Vec3d::new(0.5, -0.25, 0.1));
+    let hull = convex_hull(&points).unwrap();
+
+    assert_eq!(hull.len(), 12);
+    let mut edges: HashMap<(usize, usize), u32> = HashMap::new();
+    for &[a, b, c] in &hull {
+        assert!(a < 8 && b < 8 && c < 8);
+        // every triangle faces outwards, with all the points behind it
+        let normal = (points[b] - points[a]).cross(points[c] - points[a]);
+        for &point in &points {
+            assert!(normal.dot(point - points[a]) <= 0.0001);
+        }
+        for edge in [(a, b), (b, c), (c, a)] {
+            *edges.entry(edge).or_default() += 1;
+        }
+    }
+    // closed, each edge is used once in each direction
+    for (&(a, b), &count) in &edges {
+        assert_eq!(count, 1);
+        assert_eq!(edges.get(&(b, a)), Some(&1));
+    }
+
+    // flat point sets have no hull
+    assert!(convex_hull(&cube_verts(1.0)[..4]).is_none());
+}
+
+#[test]
+fn decimate_stays_closed() {
+    let mut sphere = sphere_subobject("sphere", 12, 24);
+    assert!(sphere.bsp_data.is_closed());
+    let before = num_polygons(&sphere);
+
+    sphere.decimate(0.25);
+    let after = num_polygons(&sphere);
+    assert!(after < before / 2, "{} -> {}", before, after);
+    assert!(after >= 4);
+    assert!(sphere.bsp_data.is_closed());
+}
+
+#[test]
+fn remove_subobject_renumbers() {
+    let mut model = test_model();
+    let base = model.get_obj_id_by_name("turret01a").unwrap();
+    let gun = model.get_obj_id_by_name("turret01b").unwrap();
+    model.paths.push(Path {
+        name: "$path01".to_string(),
+        parent: "radar".to_string(),
+        points: vec![PathPoint {
+            position: Vec3d::ZERO,
+            radius: 1.0,
+            turrets: vec![ObjectId(4)],
+        }],
+    });
+
+    let removed = model.remove_subobject(base);
+    assert_eq!(removed, vec![base, gun]);
+    assert_eq!(model.sub_objects.len(), 3);
+    assert_eq!(model.header.num_subobjects, 3);
+    assert!(model.turrets.is_empty());
+
+    // everything after the removed subobjects has moved down, with all references to them following along
+    for (i, subobj) in model.sub_objects.iter().enumerate() {
+        assert_eq!(subobj.obj_id, ObjectId(i as u32));
+    }
+    let detail0 = model.header.detail_levels[0];
+    let radar = model.get_obj_id_by_name("radar").unwrap();
+    let helper = model.get_obj_id_by_name("helper").unwrap();
+    assert_eq!((radar, helper), (ObjectId(1), ObjectId(2)));
+    assert_eq!(model.sub_objects[detail0].children().copied().collect::<Vec<_>>(), vec![radar, helper]);
+    assert_eq!(model.sub_objects[helper].parent(), Some(detail0));
+    assert_eq!(model.paths[0].points[0].turrets, vec![helper]);
+}
+
+#[test]
+fn generate_debris_names() {
+    let mut model = test_model();
+    let mut taken = cube_subobject("debris01", 1.0);
+    taken.is_debris_model = true;
+    model.add_subobject(taken, None);
+
+    let debris = model.generate_debris(3);
+    assert!(!debris.is_empty());
+    let mut names = model.sub_objects.iter().map(|subobj| subobj.name.clone()).collect::<Vec<_>>();
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
+}
+
+#[test]
+fn obj_round_trip() {
+    let model = test_model();
+    let obj_path = temp_path("round-trip.obj");
+    let mtl_path = obj_path.with_extension("mtl");
+    model
+        .write_obj(&mut std::fs::File::create(&obj_path).unwrap(), &mtl_path.file_name().unwrap().to_string_lossy(), &Default::default())
+        .unwrap();
+    model.write_mtl(&mut std::fs::File::create(&mtl_path).unwrap()).unwrap();
+    model
+        .write_obj_metadata(&mut std::fs::File::create(obj_path.with_extension(OBJ_METADATA_EXTENSION)).unwrap())
+        .unwrap();
+
+    let (read, warnings) = parse_obj(obj_path.clone(), &Default::default()).unwrap();
+    for path in [&obj_path, &mtl_path, &obj_path.with_extension(OBJ_METADATA_EXTENSION)] {
+        let _ = std::fs::remove_file(path);
+    }
+    assert!(warnings.is_empty(), "{:?}", warnings);
+
+    assert_eq!(read.sub_objects.len(), model.sub_objects.len());
+    for subobj in &model.sub_objects {
+        let id = read.get_obj_id_by_name(&subobj.name).unwrap();
+        let read_subobj = &read.sub_objects[id];
+        let parent_name = |model: &Model, subobj: &SubObject| subobj.parent().map(|parent| model.sub_objects[parent].name.clone());
+        assert_eq!(parent_name(&read, read_subobj), parent_name(&model, subobj));
+        assert_vec_eq(read_subobj.offset, subobj.offset);
+        assert_eq!(read_subobj.properties, subobj.properties);
+        assert_eq!(read_subobj.movement_type, subobj.movement_type);
+        assert_eq!(read_subobj.movement_axis, subobj.movement_axis);
+        assert_eq!(num_polygons(read_subobj), num_polygons(subobj));
+        assert_eq!(read_subobj.bsp_data.verts.len(), subobj.bsp_data.verts.len());
+    }
+
+    assert_eq!(read.turrets.len(), 1);
+    let turret = &read.turrets[0];
+    assert_eq!(read.sub_objects[turret.base_obj].name, "turret01a");
+    assert_eq!(read.sub_objects[turret.gun_obj].name, "turret01b");
+    assert_vec_eq(turret.normal.0, model.turrets[0].normal.0);
+    assert_vec_eq(turret.fire_points[0], model.turrets[0].fire_points[0]);
+}
+
+#[test]
+fn gltf_round_trip() {
+    let mut model = test_model();
+    // nodes without geometry aren't read back as subobjects from DAE or glTF
+    model.remove_subobject(model.get_obj_id_by_name("helper").unwrap());
+    let base = model.get_obj_id_by_name("turret01a").unwrap();
+    let radar = model.get_obj_id_by_name("radar").unwrap();
+    model.paths.push(Path {
+        name: "$path01".to_string(),
+        parent: "turret01a".to_string(),
+        points: vec![
+            PathPoint {
+                position: Vec3d::new(0.0, 10.0, 0.0),
+                radius: 2.0,
+                turrets: vec![base, radar],
+            },
+            PathPoint {
+                position: Vec3d::new(0.0, 12.0, 5.0),
+                radius: 1.5,
+                turrets: vec![],
+            },
+        ],
+    });
+    model.header.mass = 1234.5;
+    model.header.center_of_mass = Vec3d::new(0.5, -0.25, 1.0);
+    model.header.cross_sections = vec![(-2.0, 3.5), (2.0, 4.0)];
+    model.comments = "made for the tests\nsecond line".to_string();
+
+    for metadata_in_extras in [false, true] {
+        let path = temp_path(if metadata_in_extras { "extras.gltf" } else { "helpers.gltf" });
+        model
+            .write_gltf(std::fs::File::create(&path).unwrap(), false, None, metadata_in_extras, true, &Default::default())
+            .unwrap();
+        let result = parse_gltf(path.clone(), &Default::default());
+        let _ = std::fs::remove_file(&path);
+        let (read, _) = result.unwrap();
+
+        assert_eq!(read.sub_objects.len(), model.sub_objects.len());
+        assert_eq!(read.get_obj_id_by_name("radar").map(|id| read.sub_objects[id].properties.as_str()), Some("$rotate=5"));
+
+        assert_eq!(read.paths.len(), 1);
+        let (path, read_path) = (&model.paths[0], &read.paths[0]);
+        assert_eq!(read_path.name, path.name);
+        assert_eq!(read_path.parent, path.parent);
+        assert_eq!(read_path.points.len(), path.points.len());
+        for (point, read_point) in path.points.iter().zip(&read_path.points) {
+            assert_vec_eq(read_point.position, point.position);
+            assert!((read_point.radius - point.radius).abs() < 0.0001);
+            let names = |model: &Model, turrets: &[ObjectId]| turrets.iter().map(|&id| model.sub_objects[id].name.clone()).collect::<Vec<_>>();
+            assert_eq!(names(&read, &read_point.turrets), names(&model, &point.turrets));
+        }
+
+        assert_eq!(read.header.mass, model.header.mass);
+        assert_vec_eq(read.header.center_of_mass, model.header.center_of_mass);
+        assert_eq!(read.header.cross_sections, model.header.cross_sections);
+        assert_eq!(read.comments, model.comments);
+    }
+}
@@ -1,4 +1,6 @@
 use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display};
 use std::io::{self, Write};
@@ -119,7 +121,7 @@ macro_rules! mk_struct {
     };
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Axis {
     X,
     Y,
@@ -128,6 +130,158 @@ pub enum Axis {
 
 const ALL_AXES: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
 
+/// An axis of a DAE/glTF/OBJ file's coordinate system, pointing either way along it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedAxis {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+impl SignedAxis {
+    pub const ALL: [SignedAxis; 6] = [Self::PosX, Self::NegX, Self::PosY, Self::NegY, Self::PosZ, Self::NegZ];
+
+    pub fn axis(self) -> Axis {
+        match self {
+            Self::PosX | Self::NegX => Axis::X,
+            Self::PosY | Self::NegY => Axis::Y,
+            Self::PosZ | Self::NegZ => Axis::Z,
+        }
+    }
+
+    pub fn sign(self) -> f32 {
+        match self {
+            Self::PosX | Self::PosY | Self::PosZ => 1.0,
+            Self::NegX | Self::NegY | Self::NegZ => -1.0,
+        }
+    }
+
+    pub fn to_vec(self) -> Vec3d {
+        let mut vec = Vec3d::ZERO;
+        vec[self.axis()] = self.sign();
+        vec
+    }
+}
+impl From<UpAxis> for SignedAxis {
+    fn from(up: UpAxis) -> Self {
+        match up {
+            UpAxis::XUp => Self::PosX,
+            UpAxis::YUp => Self::PosY,
+            UpAxis::ZUp => Self::PosZ,
+        }
+    }
+}
+impl From<SignedAxis> for UpAxis {
+    fn from(axis: SignedAxis) -> Self {
+        match axis.axis() {
+            Axis::X => UpAxis::XUp,
+            Axis::Y => UpAxis::YUp,
+            Axis::Z => UpAxis::ZUp,
+        }
+    }
+}
+impl Display for SignedAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.sign() < 0.0 { '-' } else { '+' };
+        let axis = match self.axis() {
+            Axis::X => 'X',
+            Axis::Y => 'Y',
+            Axis::Z => 'Z',
+        };
+        write!(f, "{}{}", sign, axis)
+    }
+}
+
+/// The coordinate system of a DAE/glTF/OBJ file: which of its axes are POF's up and forward, how big its units are,
+/// and which way around its polygons are wound
+/// POF's right is always forward × up in the file, so that its right handed coordinates become POF's left handed ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordSystem {
+    right: SignedAxis,
+    up: SignedAxis,
+    forward: SignedAxis,
+    scale: f32,
+    flip_winding: bool,
+}
+impl CoordSystem {
+    // up and forward must be perpendicular
+    fn new(up: SignedAxis, forward: SignedAxis, scale: f32, flip_winding: bool) -> Self {
+        let right_vec = forward.to_vec().cross(up.to_vec());
+        let right = SignedAxis::ALL.into_iter().find(|axis| axis.to_vec().dot(right_vec) > 0.5).unwrap();
+        CoordSystem { right, up, forward, scale, flip_winding }
+    }
+
+    // the forward axis files with this up axis usually have, which is what pof-tools has always used
+    fn default_forward(up: SignedAxis) -> SignedAxis {
+        match up.axis() {
+            Axis::X | Axis::Z => SignedAxis::PosY,
+            Axis::Y => SignedAxis::NegZ,
+        }
+    }
+
+    pub fn up(&self) -> SignedAxis {
+        self.up
+    }
+
+    pub fn forward(&self) -> SignedAxis {
+        self.forward
+    }
+
+    /// POF units (meters) per unit of the file
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Whether the file's polygons are wound the same way as FSO's, rather than the other way around as usual
+    pub fn flip_winding(&self) -> bool {
+        self.flip_winding
+    }
+}
+impl From<UpAxis> for CoordSystem {
+    fn from(up: UpAxis) -> Self {
+        let up = SignedAxis::from(up);
+        CoordSystem::new(up, CoordSystem::default_forward(up), 1.0, false)
+    }
+}
+
+/// How the axes and units of DAE, glTF and OBJ files are converted to and from POF's, when importing or exporting them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImportOptions {
+    /// The file's up axis, or `None` for the one the file says it uses (DAE), or `+Y` (glTF and OBJ)
+    pub up_axis: Option<SignedAxis>,
+    /// The file's forward axis, or `None` for the usual one for its up axis; ignored if it isn't perpendicular to up
+    pub forward_axis: Option<SignedAxis>,
+    /// POF units (meters) per unit of the file, e.g. `0.01` for a file in centimeters
+    pub scale: f32,
+    /// Whether the file's polygons are wound the other way around, which turns them inside out
+    pub flip_winding: bool,
+}
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            up_axis: None,
+            forward_axis: None,
+            scale: 1.0,
+            flip_winding: false,
+        }
+    }
+}
+impl ImportOptions {
+    /// The coordinate system to use for a file, given the up axis the file itself claims to use
+    pub fn coords(&self, file_up: UpAxis) -> CoordSystem {
+        let up = self.up_axis.unwrap_or_else(|| file_up.into());
+        let forward = self
+            .forward_axis
+            .filter(|forward| forward.axis() != up.axis())
+            .unwrap_or_else(|| CoordSystem::default_forward(up));
+        // a scale of zero or less (or not a number at all) would make a mess of all the geometry, so it's ignored
+        let scale = if self.scale.is_finite() && self.scale > 0.0 { self.scale } else { 1.0 };
+        CoordSystem::new(up, forward, scale, self.flip_winding)
+    }
+}
+
 mk_struct! {
     #[derive(Clone, Copy, Default)]
     pub struct Vec3d {
@@ -235,6 +389,16 @@ impl Vec3d {
         let mag = self.magnitude();
         self * (1.0 / mag)
     }
+    pub fn dot(self, other: Vec3d) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn cross(self, other: Vec3d) -> Vec3d {
+        Vec3d {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
     pub fn is_null(self) -> bool {
         self.x.abs() <= 0.000001 && self.y.abs() <= 0.000001 && self.z.abs() <= 0.000001
     }
@@ -251,23 +415,34 @@ impl Vec3d {
         out
     }
 
-    /// Swizzle coordinates from POF (Right: `+x`, Up: `+y`, In: `-z`) to specified DAE convention
-    pub fn from_coord(&self, up: UpAxis) -> Vec3d {
-        match up {
-            UpAxis::XUp => Vec3d { x: -self.z, y: self.x, z: self.y },
-            UpAxis::YUp => Vec3d { x: self.x, y: self.y, z: -self.z },
-            UpAxis::ZUp => Vec3d { x: self.x, y: self.z, z: self.y },
-        }
+    /// Converts a position from the given file coordinate system to POF's (Right: `+x`, Up: `+y`, Forward: `+z`), in POF units
+    pub fn from_coord(&self, coords: CoordSystem) -> Vec3d {
+        self.from_coord_dir(coords) * coords.scale
     }
 
-    /// Swizzle coordinates from specified DAE convention to POF (Right: `+x`, Up: `+y`, In: `-z`)
-    pub fn to_coord(&self, up: UpAxis) -> Vec3d {
-        match up {
-            UpAxis::XUp => Vec3d { x: self.y, y: self.z, z: -self.x },
-            UpAxis::YUp => Vec3d { x: self.x, y: self.y, z: -self.z },
-            UpAxis::ZUp => Vec3d { x: self.x, y: self.z, z: self.y },
+    /// Converts a position from POF's coordinate system to the given file coordinate system, in the file's units
+    pub fn to_coord(&self, coords: CoordSystem) -> Vec3d {
+        self.to_coord_dir(coords) / coords.scale
+    }
+
+    /// Like [`Vec3d::from_coord`], for directions (normals etc), which aren't scaled
+    pub fn from_coord_dir(&self, coords: CoordSystem) -> Vec3d {
+        let component = |axis: SignedAxis| self[axis.axis()] * axis.sign();
+        Vec3d {
+            x: component(coords.right),
+            y: component(coords.up),
+            z: component(coords.forward),
         }
     }
+
+    /// Like [`Vec3d::to_coord`], for directions (normals etc), which aren't scaled
+    pub fn to_coord_dir(&self, coords: CoordSystem) -> Vec3d {
+        let mut out = Vec3d::ZERO;
+        out[coords.right.axis()] = self.x * coords.right.sign();
+        out[coords.up.axis()] = self.y * coords.up.sign();
+        out[coords.forward.axis()] = self.z * coords.forward.sign();
+        out
+    }
 }
 impl Add for Vec3d {
     type Output = Vec3d;
@@ -622,6 +797,70 @@ impl Serialize for PolyVertex<()> {
         self.uv.write_to(w)
     }
 }
+impl PolyVertex {
+    pub fn key(&self) -> PolyVertexKey {
+        PolyVertexKey {
+            vertex_id: self.vertex_id,
+            normal_id: self.normal_id,
+            uv: [self.uv.0.to_bits(), self.uv.1.to_bits()],
+        }
+    }
+}
+
+/// Identifies a polygon vertex by everything a POF stores for it, which is all [`VertexExtras`] has to go on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PolyVertexKey {
+    pub vertex_id: VertexId,
+    pub normal_id: NormalId,
+    uv: [u32; 2],
+}
+
+/// Per-vertex data from DAE and glTF files which POFs have no room for (further uv sets, vertex colors),
+/// kept around so it can be exported again, but dropped when saving a POF
+///
+/// It's keyed by [`PolyVertexKey`], so polygon corners sharing a vertex, normal and uv share their extras too.
+/// Imports merge vertices by position and normal and give untextured meshes a uv of (0, 0), so corners which
+/// only differ in their color (or further uvs) end up with one of them.
+#[derive(Debug, Clone, Default)]
+pub struct VertexExtras {
+    /// The uv sets after the first one, which is [`PolyVertex::uv`]
+    pub uv_sets: Vec<HashMap<PolyVertexKey, (f32, f32)>>,
+    /// RGBA, empty if the file had no vertex colors
+    pub colors: HashMap<PolyVertexKey, [f32; 4]>,
+}
+impl VertexExtras {
+    pub fn is_empty(&self) -> bool {
+        self.uv_sets.is_empty() && self.colors.is_empty()
+    }
+
+    // the uv of a polygon vertex in one of the extra sets, 0 being the first *extra* set
+    pub fn uv(&self, set: usize, polyvert: &PolyVertex) -> (f32, f32) {
+        self.uv_sets[set].get(&polyvert.key()).copied().unwrap_or_default()
+    }
+
+    // vertices which weren't given a color are white
+    pub fn color(&self, polyvert: &PolyVertex) -> [f32; 4] {
+        self.colors.get(&polyvert.key()).copied().unwrap_or([1.0; 4])
+    }
+
+    // carries the data over to polygon vertices whose ids have changed, anything not in the map is lost
+    pub fn remap(&self, key_map: &HashMap<PolyVertexKey, PolyVertexKey>) -> Option<VertexExtras> {
+        let remap = |key| key_map.get(key).copied();
+        let extras = VertexExtras {
+            uv_sets: self
+                .uv_sets
+                .iter()
+                .map(|set| set.iter().filter_map(|(key, &uv)| Some((remap(key)?, uv))).collect())
+                .collect(),
+            colors: self.colors.iter().filter_map(|(key, &color)| Some((remap(key)?, color))).collect(),
+        };
+        if extras.is_empty() {
+            None
+        } else {
+            Some(extras)
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Color {
@@ -653,6 +892,22 @@ impl Debug for ShieldPolygon {
         )
     }
 }
+impl ShieldPolygon {
+    // the directed edges of this polygon, in winding order
+    pub fn edges(&self) -> [(VertexId, VertexId); 3] {
+        let (a, b, c) = self.verts;
+        [(a, b), (b, c), (c, a)]
+    }
+
+    // reverses the winding order, and the normal along with it
+    pub fn flip(&mut self) {
+        let (a, b, c) = self.verts;
+        self.verts = (a, c, b);
+        let (n1, n2, n3) = self.neighbors;
+        self.neighbors = (n3, n2, n1);
+        self.normal = -self.normal;
+    }
+}
 impl Serialize for ShieldPolygon {
     fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
         let ShieldPolygon { normal, verts: (x, y, z), neighbors } = self;
@@ -801,6 +1056,198 @@ pub struct ObjHeader {
     pub bsp_lights: Vec<BspLight>,
 }
 
+// builds the convex hull of some points, by adding them one at a time and replacing whatever faces each one can see
+// returns outward facing triangles as indices into points, or None if the points don't have any volume to them
+pub fn convex_hull(points: &[Vec3d]) -> Option<Vec<[usize; 3]>> {
+    struct Face {
+        verts: [usize; 3],
+        normal: Vec3d,
+        dist: f32,
+        outside: Vec<usize>,
+        alive: bool,
+    }
+
+    let make_face = |verts: [usize; 3]| {
+        let [a, b, c] = verts.map(|i| points[i]);
+        let normal = (b - a).cross(c - a).normalize();
+        Face {
+            verts,
+            normal,
+            dist: normal.dot(a),
+            outside: vec![],
+            alive: true,
+        }
+    };
+
+    let bbox = BoundingBox::from_vectors(points.iter().copied());
+    let epsilon = (bbox.max - bbox.min).magnitude() * 0.00001;
+    if points.len() < 4 || epsilon <= 0.0 {
+        return None;
+    }
+
+    // find some starting tetrahedron, as big as is reasonably easy to find
+    let farthest_from = |dist: &dyn Fn(Vec3d) -> f32| {
+        (0..points.len())
+            .max_by(|&a, &b| dist(points[a]).partial_cmp(&dist(points[b])).unwrap())
+            .unwrap()
+    };
+    let i0 = farthest_from(&|point| -point.x);
+    let i1 = farthest_from(&|point| (point - points[i0]).magnitude());
+    let line = (points[i1] - points[i0]).normalize();
+    let i2 = farthest_from(&|point| (point - points[i0]).cross(line).magnitude());
+    let plane_normal = (points[i1] - points[i0]).cross(points[i2] - points[i0]).normalize();
+    let i3 = farthest_from(&|point| (point - points[i0]).dot(plane_normal).abs());
+
+    if (points[i1] - points[i0]).magnitude() <= epsilon
+        || (points[i2] - points[i0]).cross(line).magnitude() <= epsilon
+        || (points[i3] - points[i0]).dot(plane_normal).abs() <= epsilon
+    {
+        return None;
+    }
+
+    let (i1, i2) = if (points[i3] - points[i0]).dot(plane_normal) > 0.0 {
+        (i2, i1)
+    } else {
+        (i1, i2)
+    };
+    // quickhull: every point outside the hull is assigned to a face it's in front of, and the hull grows by always adding
+    // the farthest point of some face, which keeps slivers (and the numerical trouble that comes with them) to a minimum
+    let mut faces = vec![];
+    let mut edge_faces: HashMap<(usize, usize), usize> = HashMap::new();
+    let add_face = |faces: &mut Vec<Face>, edge_faces: &mut HashMap<(usize, usize), usize>, verts: [usize; 3]| {
+        let [a, b, c] = verts;
+        for edge in [(a, b), (b, c), (c, a)] {
+            edge_faces.insert(edge, faces.len());
+        }
+        faces.push(make_face(verts));
+    };
+    for verts in [[i0, i1, i2], [i0, i3, i1], [i1, i3, i2], [i2, i3, i0]] {
+        add_face(&mut faces, &mut edge_faces, verts);
+    }
+
+    // gives each point to whichever of the faces it's farthest in front of, if any
+    let assign_points = |faces: &mut Vec<Face>, candidates: &[usize], points_to_assign: &mut dyn Iterator<Item = usize>| {
+        for i in points_to_assign {
+            let best = candidates
+                .iter()
+                .map(|&face| (face, faces[face].normal.dot(points[i]) - faces[face].dist))
+                .filter(|&(_, dist)| dist > epsilon)
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            if let Some((face, _)) = best {
+                faces[face].outside.push(i);
+            }
+        }
+    };
+    assign_points(&mut faces, &[0, 1, 2, 3], &mut (0..points.len()).filter(|&i| ![i0, i1, i2, i3].contains(&i)));
+
+    while let Some(start) = faces.iter().position(|face| face.alive && !face.outside.is_empty()) {
+        let eye = *faces[start]
+            .outside
+            .iter()
+            .max_by(|&&a, &&b| {
+                let dist = |i: usize| faces[start].normal.dot(points[i]);
+                dist(a).partial_cmp(&dist(b)).unwrap()
+            })
+            .unwrap();
+
+        // flood out from the starting face to find every face the eye can see, the horizon is where that stops
+        let mut visible = vec![start];
+        faces[start].alive = false;
+        let mut horizon = vec![];
+        let mut i = 0;
+        while i < visible.len() {
+            let [a, b, c] = faces[visible[i]].verts;
+            for (a, b) in [(a, b), (b, c), (c, a)] {
+                let neighbor = edge_faces[&(b, a)];
+                if !faces[neighbor].alive {
+                    continue;
+                }
+                if faces[neighbor].normal.dot(points[eye]) - faces[neighbor].dist > epsilon {
+                    faces[neighbor].alive = false;
+                    visible.push(neighbor);
+                } else {
+                    horizon.push((a, b));
+                }
+            }
+            i += 1;
+        }
+
+        let new_faces = (faces.len()..faces.len() + horizon.len()).collect::<Vec<_>>();
+        for (a, b) in horizon {
+            add_face(&mut faces, &mut edge_faces, [a, b, eye]);
+        }
+
+        let orphans = visible
+            .iter()
+            .flat_map(|&face| std::mem::take(&mut faces[face].outside))
+            .filter(|&i| i != eye)
+            .collect::<Vec<_>>();
+        assign_points(&mut faces, &new_faces, &mut orphans.into_iter());
+    }
+
+    Some(faces.into_iter().filter(|face| face.alive).map(|face| face.verts).collect())
+}
+
+// evenly spread points on a unit sphere
+fn fibonacci_sphere(num_points: usize) -> impl Iterator<Item = Vec3d> {
+    let golden_angle = std::f32::consts::PI * (3.0 - f32::sqrt(5.0));
+    (0..num_points).map(move |i| {
+        let y = 1.0 - 2.0 * (i as f32 + 0.5) / num_points as f32;
+        let ring_radius = f32::sqrt(1.0 - y * y);
+        let theta = golden_angle * i as f32;
+        Vec3d::new(theta.cos() * ring_radius, y, theta.sin() * ring_radius)
+    })
+}
+
+// the points which are farthest along each of the given directions, without duplicates
+fn support_points(points: &[Vec3d], directions: impl Iterator<Item = Vec3d>) -> Vec<Vec3d> {
+    let mut indices = directions
+        .filter_map(|dir| (0..points.len()).max_by(|&a, &b| points[a].dot(dir).partial_cmp(&points[b].dot(dir)).unwrap()))
+        .collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices.dedup();
+    indices.into_iter().map(|i| points[i]).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShieldShape {
+    // the convex hull of the model, simplified to fit in the triangle budget
+    ConvexHull,
+    // an ellipsoid fitted to the model's proportions, and then shrunk down onto its convex hull
+    Ellipsoid,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShieldGenerationOptions {
+    pub shape: ShieldShape,
+    pub max_triangles: usize,
+    // how far the shield is pushed outwards from the model
+    pub padding: f32,
+}
+impl Default for ShieldGenerationOptions {
+    fn default() -> Self {
+        Self {
+            shape: ShieldShape::ConvexHull,
+            max_triangles: 200,
+            padding: 0.0,
+        }
+    }
+}
+
+// problems with the shape of a shield mesh, which should be a closed surface
+#[derive(Debug, Default)]
+pub struct ShieldTopologyReport {
+    // edges without a polygon on the other side
+    pub open_edges: Vec<(VertexId, VertexId)>,
+    // edges which more than one polygon winds in the same direction, meaning something is flipped or non-manifold
+    pub duplicate_edges: Vec<(VertexId, VertexId)>,
+}
+impl ShieldTopologyReport {
+    pub fn is_ok(&self) -> bool {
+        self.open_edges.is_empty() && self.duplicate_edges.is_empty()
+    }
+}
+
 #[derive(Debug)]
 pub struct ShieldData {
     pub verts: Vec<Vec3d>,
@@ -808,6 +1255,288 @@ pub struct ShieldData {
     pub collision_tree: Option<ShieldNode>,
 }
 impl ShieldData {
+    // generates a low poly mesh enclosing the detail0 hierarchy of a model, or None if there isn't enough geometry to enclose
+    pub fn generate(model: &Model, options: &ShieldGenerationOptions) -> Option<ShieldData> {
+        let points = model
+            .detail0_subobjects()
+            .into_iter()
+            .flat_map(|(subobj, offset)| subobj.bsp_data.verts.iter().map(move |vert| *vert + offset))
+            .collect::<Vec<_>>();
+
+        let (verts, tris) = match options.shape {
+            ShieldShape::ConvexHull => {
+                // a hull of n points can have at most 2n - 4 triangles, so pick out that many extreme points to build it from
+                let hull_points = support_points(&points, fibonacci_sphere(options.max_triangles.max(4) / 2 + 2));
+                let tris = convex_hull(&hull_points)?;
+
+                // push each vertex outwards, along the average normal of its faces
+                let mut vert_normals = vec![Vec3d::ZERO; hull_points.len()];
+                for &[a, b, c] in &tris {
+                    let normal = (hull_points[b] - hull_points[a]).cross(hull_points[c] - hull_points[a]).normalize();
+                    for i in [a, b, c] {
+                        vert_normals[i] += normal;
+                    }
+                }
+                let verts = hull_points
+                    .iter()
+                    .zip(vert_normals)
+                    .map(|(&point, normal)| {
+                        if normal.is_null() {
+                            point
+                        } else {
+                            point + normal.normalize() * options.padding
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                (verts, tris)
+            }
+            ShieldShape::Ellipsoid => {
+                // a reasonably detailed hull to shrink onto
+                let hull_points = support_points(&points, fibonacci_sphere(512));
+                let hull_planes = convex_hull(&hull_points)?
+                    .into_iter()
+                    .map(|[a, b, c]| {
+                        let normal = (hull_points[b] - hull_points[a]).cross(hull_points[c] - hull_points[a]).normalize();
+                        (normal, normal.dot(hull_points[a]))
+                    })
+                    .collect::<Vec<_>>();
+
+                let center = Vec3d::average(hull_points.iter().copied());
+                let bbox = BoundingBox::from_vectors(hull_points.iter().copied());
+                let half_extents = (bbox.max - bbox.min) / 2.0;
+
+                // a uv sphere, with twice as many segments as rings, has 4 * rings * (rings - 1) triangles
+                let rings = ((1.0 + f32::sqrt(1.0 + options.max_triangles as f32)) / 2.0).floor().max(2.0) as usize;
+                let segments = rings * 2;
+
+                let mut directions = vec![Vec3d::new(0.0, 1.0, 0.0)];
+                for ring in 1..rings {
+                    let (ring_radius, y) = (std::f32::consts::PI * ring as f32 / rings as f32).sin_cos();
+                    for segment in 0..segments {
+                        let (z, x) = (std::f32::consts::TAU * segment as f32 / segments as f32).sin_cos();
+                        directions.push(Vec3d::new(x * ring_radius, y, z * ring_radius));
+                    }
+                }
+                directions.push(Vec3d::new(0.0, -1.0, 0.0));
+
+                // shrink each point of the ellipsoid down along its ray from the center, until it hits the hull
+                let mut verts = directions
+                    .into_iter()
+                    .map(|dir| {
+                        let dir = Vec3d::new(dir.x * half_extents.x, dir.y * half_extents.y, dir.z * half_extents.z).normalize();
+                        let dist = hull_planes
+                            .iter()
+                            .filter(|(normal, _)| normal.dot(dir) > 0.0)
+                            .map(|&(normal, plane_dist)| (plane_dist - normal.dot(center)) / normal.dot(dir))
+                            .fold(f32::INFINITY, f32::min);
+                        center + dir * (dist + options.padding)
+                    })
+                    .collect::<Vec<_>>();
+
+                let ring_vert = |ring: usize, segment: usize| 1 + (ring - 1) * segments + segment % segments;
+                let bottom = verts.len() - 1;
+                let mut tris = vec![];
+                for segment in 0..segments {
+                    tris.push([0, ring_vert(1, segment + 1), ring_vert(1, segment)]);
+                    for ring in 1..rings - 1 {
+                        let (a, b) = (ring_vert(ring, segment), ring_vert(ring, segment + 1));
+                        let (c, d) = (ring_vert(ring + 1, segment), ring_vert(ring + 1, segment + 1));
+                        tris.push([a, b, d]);
+                        tris.push([a, d, c]);
+                    }
+                    tris.push([bottom, ring_vert(rings - 1, segment), ring_vert(rings - 1, segment + 1)]);
+                }
+
+                // the triangles between those points are chords, which cut into the hull, so push their corners out along their
+                // rays until every hull point behind a triangle (as seen from the center) is inside it by the padding as well
+                let mut pushes = vec![0.0_f32; verts.len()];
+                for tri in &tris {
+                    let [va, vb, vc] = tri.map(|i| verts[i] - center);
+                    let mut normal = (vb - va).cross(vc - va).normalize();
+                    if normal.dot(va + vb + vc) < 0.0 {
+                        normal = -normal;
+                    }
+                    let sides = [(va.cross(vb), vc), (vb.cross(vc), va), (vc.cross(va), vb)];
+                    let sag = hull_points
+                        .iter()
+                        .map(|&point| point - center)
+                        .filter(|&point| sides.iter().all(|&(side, opposite)| side.dot(point) * side.dot(opposite) >= 0.0))
+                        .map(|point| normal.dot(point - va) + options.padding)
+                        .fold(0.0, f32::max);
+                    for (i, vert) in tri.iter().zip([va, vb, vc]) {
+                        let cos = normal.dot(vert.normalize());
+                        if cos > 0.001 {
+                            pushes[*i] = pushes[*i].max(sag / cos);
+                        }
+                    }
+                }
+                for (vert, push) in verts.iter_mut().zip(pushes) {
+                    *vert += (*vert - center).normalize() * push;
+                }
+
+                (verts, tris)
+            }
+        };
+
+        // only keep the vertices which are actually used
+        let mut vert_map: HashMap<usize, VertexId> = HashMap::new();
+        let mut shield_verts = vec![];
+        let polygons = tris
+            .into_iter()
+            .map(|tri| {
+                let [a, b, c] = tri.map(|i| {
+                    *vert_map.entry(i).or_insert_with(|| {
+                        shield_verts.push(verts[i]);
+                        VertexId(shield_verts.len() as u32 - 1)
+                    })
+                });
+                let [v1, v2, v3] = tri.map(|i| verts[i]);
+                ShieldPolygon {
+                    normal: (v2 - v1).cross(v3 - v1).normalize(),
+                    verts: (a, b, c),
+                    neighbors: Default::default(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut shield_data = ShieldData { verts: shield_verts, polygons, collision_tree: None };
+        shield_data.recalc_neighbors();
+        shield_data.collision_tree = Some(ShieldData::recalculate_tree(&shield_data.verts, &shield_data.polygons));
+        Some(shield_data)
+    }
+
+    // finds the neighbors of each polygon, where each neighbor is the polygon across the edge starting at the corresponding vertex
+    // also reports edges with no polygon on the other side, and directed edges used by more than one polygon
+    fn find_neighbors(&self) -> (Vec<(PolygonId, PolygonId, PolygonId)>, ShieldTopologyReport) {
+        let mut report = ShieldTopologyReport::default();
+
+        // create a map keyed on each vertex pair, based on winding order, where the value is the polygon id
+        let mut map: HashMap<(VertexId, VertexId), PolygonId> = HashMap::new();
+        for (i, poly) in self.polygons.iter().enumerate() {
+            for edge in poly.edges() {
+                if map.insert(edge, PolygonId(i as u32)).is_some() {
+                    report.duplicate_edges.push(edge);
+                }
+            }
+        }
+
+        // for each polygon then, by swapping its vertex pairs, you can grab each adjacent polygon
+        let neighbors = self
+            .polygons
+            .iter()
+            .map(|poly| {
+                let [neighbor1, neighbor2, neighbor3] = poly.edges().map(|(a, b)| match map.get(&(b, a)) {
+                    Some(&id) => id,
+                    None => {
+                        report.open_edges.push((a, b));
+                        PolygonId(0)
+                    }
+                });
+                (neighbor1, neighbor2, neighbor3)
+            })
+            .collect();
+
+        (neighbors, report)
+    }
+
+    pub fn check_topology(&self) -> ShieldTopologyReport {
+        self.find_neighbors().1
+    }
+
+    // reassigns every polygon's neighbors, open edges can't have a neighbor and are given polygon 0
+    pub fn recalc_neighbors(&mut self) -> ShieldTopologyReport {
+        let (neighbors, report) = self.find_neighbors();
+        for (poly, neighbors) in self.polygons.iter_mut().zip(neighbors) {
+            poly.neighbors = neighbors;
+        }
+        report
+    }
+
+    // makes the winding of all polygons consistent with their neighbors and facing outwards, and fills in any holes
+    // bounded by at most max_hole_edges edges, then recalculates the neighbors and collision tree
+    pub fn repair(&mut self, max_hole_edges: usize) -> ShieldTopologyReport {
+        // first, walk across the polygons flipping any neighbor that winds the shared edge the same way
+        let mut edge_polys: HashMap<(VertexId, VertexId), Vec<usize>> = HashMap::new();
+        for (i, poly) in self.polygons.iter().enumerate() {
+            for (a, b) in poly.edges() {
+                edge_polys.entry((a.min(b), a.max(b))).or_default().push(i);
+            }
+        }
+
+        let mut visited = vec![false; self.polygons.len()];
+        for start in 0..self.polygons.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut component = vec![start];
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                for (a, b) in self.polygons[i].edges() {
+                    for &other in &edge_polys[&(a.min(b), a.max(b))] {
+                        if !visited[other] {
+                            visited[other] = true;
+                            if self.polygons[other].edges().contains(&(a, b)) {
+                                self.polygons[other].flip();
+                            }
+                            component.push(other);
+                            stack.push(other);
+                        }
+                    }
+                }
+            }
+
+            // then if this whole piece is inside out, flip all of it
+            let signed_volume: f32 = component
+                .iter()
+                .map(|&i| {
+                    let (a, b, c) = self.polygons[i].verts;
+                    let [a, b, c] = [a, b, c].map(|id| self.verts[id.0 as usize]);
+                    a.dot(b.cross(c))
+                })
+                .sum();
+            if signed_volume < 0.0 {
+                for &i in &component {
+                    self.polygons[i].flip();
+                }
+            }
+        }
+
+        // now find the holes, each open edge a -> b needs a polygon with b -> a, so follow those to get the loops around the holes
+        let mut hole_edges: HashMap<VertexId, VertexId> = HashMap::new();
+        for (a, b) in self.check_topology().open_edges {
+            hole_edges.insert(b, a);
+        }
+        while let Some(&start) = hole_edges.keys().next() {
+            let mut hole = vec![start];
+            let mut cur = hole_edges.remove(&start).unwrap();
+            while cur != start {
+                hole.push(cur);
+                match hole_edges.remove(&cur) {
+                    Some(next) => cur = next,
+                    None => break, // not a proper loop, leave it be
+                }
+            }
+
+            if cur == start && hole.len() >= 3 && hole.len() <= max_hole_edges {
+                for pair in hole[1..].windows(2) {
+                    let verts = (hole[0], pair[0], pair[1]);
+                    let [v1, v2, v3] = [verts.0, verts.1, verts.2].map(|id| self.verts[id.0 as usize]);
+                    self.polygons.push(ShieldPolygon {
+                        normal: (v2 - v1).cross(v3 - v1).normalize(),
+                        verts,
+                        neighbors: Default::default(),
+                    });
+                }
+            }
+        }
+
+        let report = self.recalc_neighbors();
+        self.collision_tree = Some(ShieldData::recalculate_tree(&self.verts, &self.polygons));
+        report
+    }
+
     pub fn recalculate_tree(verts: &[Vec3d], polygons: &[ShieldPolygon]) -> ShieldNode {
         // these structs make up the smallest bits of data we'll need for this
         // the regular data structure isn't well-optimized for this, so its easier to make something purpose built
@@ -874,6 +1603,111 @@ pub struct Polygon {
     pub texture: TextureId,
     pub verts: Vec<PolyVertex>,
 }
+impl Polygon {
+    // Newell's method, which unlike a single cross product gives a sensible normal for concave and non-planar polygons
+    // not normalized, its magnitude is twice the (projected) area of the polygon
+    pub fn newell_normal(&self, verts: &[Vec3d]) -> Vec3d {
+        let mut normal = Vec3d::ZERO;
+        for (i, polyvert) in self.verts.iter().enumerate() {
+            let cur = verts[polyvert.vertex_id.0 as usize];
+            let next = verts[self.verts[(i + 1) % self.verts.len()].vertex_id.0 as usize];
+            normal.x += (cur.y - next.y) * (cur.z + next.z);
+            normal.y += (cur.z - next.z) * (cur.x + next.x);
+            normal.z += (cur.x - next.x) * (cur.y + next.y);
+        }
+        normal
+    }
+
+    // the greatest distance of any of this polygon's vertices from its plane
+    pub fn planarity_error(&self, verts: &[Vec3d]) -> f32 {
+        let normal = self.newell_normal(verts);
+        if normal.is_null() {
+            return 0.0;
+        }
+        let normal: Vec3 = normal.normalize().into();
+        let center: Vec3 = Vec3d::average(self.verts.iter().map(|polyvert| verts[polyvert.vertex_id.0 as usize])).into();
+
+        self.verts
+            .iter()
+            .map(|polyvert| (Vec3::from(verts[polyvert.vertex_id.0 as usize]) - center).dot(&normal).abs())
+            .fold(0.0, f32::max)
+    }
+
+    pub fn is_planar(&self, verts: &[Vec3d], tolerance: f32) -> bool {
+        self.planarity_error(verts) <= tolerance
+    }
+
+    // splits this polygon into triangles by ear clipping, which handles concave polygons as well
+    // the polygon is projected onto its own (Newell) plane first, so slightly non-planar polygons work too
+    pub fn triangulate(&self, verts: &[Vec3d]) -> Vec<Polygon> {
+        if self.verts.len() <= 3 {
+            return vec![self.clone()];
+        }
+
+        let make_tri = |[a, b, c]: [usize; 3]| Polygon {
+            normal: self.normal,
+            texture: self.texture,
+            verts: vec![self.verts[a].clone(), self.verts[b].clone(), self.verts[c].clone()],
+        };
+
+        let normal = self.newell_normal(verts);
+        if normal.is_null() {
+            // degenerate, nothing sensible to do but fan it
+            return (1..self.verts.len() - 1).map(|i| make_tri([0, i, i + 1])).collect();
+        }
+
+        // build a basis on the polygon's plane, such that the polygon winds counter-clockwise in it
+        let normal: Vec3 = normal.normalize().into();
+        let u = if normal.x.abs() < 0.9 {
+            normal.cross(&Vec3::x())
+        } else {
+            normal.cross(&Vec3::y())
+        }
+        .normalize();
+        let v = normal.cross(&u);
+        let points = self
+            .verts
+            .iter()
+            .map(|polyvert| {
+                let point = Vec3::from(verts[polyvert.vertex_id.0 as usize]);
+                (point.dot(&u), point.dot(&v))
+            })
+            .collect::<Vec<_>>();
+
+        fn cross((ax, ay): (f32, f32), (bx, by): (f32, f32), (cx, cy): (f32, f32)) -> f32 {
+            (bx - ax) * (cy - by) - (by - ay) * (cx - bx)
+        }
+
+        fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+            cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+        }
+
+        let mut remaining = (0..self.verts.len()).collect::<Vec<_>>();
+        let mut tris = vec![];
+        while remaining.len() > 3 {
+            let len = remaining.len();
+            let corner = |i: usize| [remaining[(i + len - 1) % len], remaining[i], remaining[(i + 1) % len]];
+
+            // an ear is a convex corner with no other vertices inside of it
+            let ear = (0..len)
+                .find(|&i| {
+                    let [a, b, c] = corner(i).map(|idx| points[idx]);
+                    cross(a, b, c) > 0.0
+                        && !remaining.iter().any(|&j| {
+                            let p = points[j];
+                            p != a && p != b && p != c && in_triangle(p, a, b, c)
+                        })
+                })
+                .unwrap_or(0); // self-intersecting or otherwise degenerate, just clip whatever
+
+            tris.push(corner(ear));
+            remaining.remove(ear);
+        }
+        tris.push([remaining[0], remaining[1], remaining[2]]);
+
+        tris.into_iter().map(make_tri).collect()
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum BspNode {
@@ -996,6 +1830,7 @@ pub struct BspData {
     pub verts: Vec<Vec3d>,
     pub norms: Vec<Vec3d>,
     pub collision_tree: BspNode,
+    pub extras: Option<VertexExtras>, // only ever from DAE or glTF imports, never written to a POF
 }
 impl BspData {
     pub(crate) const ENDOFBRANCH: u32 = 0;
@@ -1064,6 +1899,57 @@ impl BspData {
             recalc_recurse(&mut polygons.iter().collect::<Vec<_>>())
         }
     }
+
+    // builds a new BspData out of some subset of this one's polygons, keeping only the vertices and normals they actually use
+    pub fn extract_polygons(&self, polygons: impl Iterator<Item = Polygon>) -> BspData {
+        let mut vert_map: HashMap<VertexId, VertexId> = HashMap::new();
+        let mut norm_map: HashMap<NormalId, NormalId> = HashMap::new();
+        let mut key_map = HashMap::new();
+        let mut verts = vec![];
+        let mut norms = vec![];
+
+        let polygons = polygons
+            .map(|mut poly| {
+                for polyvert in &mut poly.verts {
+                    let (vertex_id, normal_id, old_key) = (polyvert.vertex_id, polyvert.normal_id, polyvert.key());
+                    polyvert.vertex_id = *vert_map.entry(vertex_id).or_insert_with(|| {
+                        verts.push(self.verts[vertex_id.0 as usize]);
+                        VertexId(verts.len() as u32 - 1)
+                    });
+                    polyvert.normal_id = *norm_map.entry(normal_id).or_insert_with(|| {
+                        norms.push(self.norms[normal_id.0 as usize]);
+                        NormalId(norms.len() as u32 - 1)
+                    });
+                    key_map.insert(old_key, polyvert.key());
+                }
+                poly
+            })
+            .collect::<Vec<_>>();
+
+        BspData {
+            collision_tree: BspData::recalculate(&verts, polygons.into_iter()),
+            verts,
+            norms,
+            extras: self.extras.as_ref().and_then(|extras| extras.remap(&key_map)),
+        }
+    }
+}
+impl BspData {
+    // whether the polygons form closed surfaces, where every edge is shared by exactly one other polygon going the other way
+    // vertices are compared by position, since duplicates may have been split off for uvs or normals
+    pub fn is_closed(&self) -> bool {
+        let mut edges: HashMap<(Vec3d, Vec3d), u32> = HashMap::new();
+        for (_, poly) in self.collision_tree.leaves() {
+            for (i, polyvert) in poly.verts.iter().enumerate() {
+                let a = self.verts[polyvert.vertex_id.0 as usize];
+                let b = self.verts[poly.verts[(i + 1) % poly.verts.len()].vertex_id.0 as usize];
+                if a != b {
+                    *edges.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+        edges.iter().all(|(&(a, b), count)| edges.get(&(b, a)) == Some(count))
+    }
 }
 impl Serialize for BspData {
     fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
@@ -1168,6 +2054,9 @@ impl Default for SubsysMovementAxis {
 
 pub const MAX_DEBRIS_OBJECTS: u32 = 32;
 
+// in order of preference
+pub const TEXTURE_EXTENSIONS: [&str; 2] = ["png", "dds"];
+
 #[derive(Debug, Clone, Default)]
 pub struct SubObject {
     pub obj_id: ObjectId,
@@ -1209,8 +2098,10 @@ impl SubObject {
     }
 
     pub fn recalc_bbox(&mut self) {
-        self.bbox.min = self.bsp_data.verts[0];
-        self.bbox.max = self.bsp_data.verts[0];
+        // subobjects without geometry, like helpers, get an empty box at their origin
+        let first = self.bsp_data.verts.first().copied().unwrap_or_default();
+        self.bbox.min = first;
+        self.bbox.max = first;
 
         for vert in &self.bsp_data.verts {
             if vert.x < self.bbox.min.x {
@@ -1237,15 +2128,368 @@ impl SubObject {
         //     BspNode::Leaf { bbox, .. } => bbox,
         // };
     }
-}
-impl Serialize for SubObject {
-    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
-        let version = get_version();
-        self.obj_id.write_to(w)?;
-        if version >= Version::V21_16 {
-            self.radius.write_to(w)?;
-            self.parent.unwrap_or(ObjectId(u32::MAX)).write_to(w)?;
-            self.offset.write_to(w)?;
+
+    // triangulates every polygon which passes the filter, and rebuilds the bsp tree if any were
+    // returns the number of polygons which were triangulated
+    fn triangulate_filtered(&mut self, filter: impl Fn(&Polygon, &[Vec3d]) -> bool) -> usize {
+        let verts = &self.bsp_data.verts;
+        let mut num_triangulated = 0;
+        let mut polygons = vec![];
+        for (_, poly) in self.bsp_data.collision_tree.leaves() {
+            if poly.verts.len() > 3 && filter(poly, verts) {
+                num_triangulated += 1;
+                polygons.extend(poly.triangulate(verts));
+            } else {
+                polygons.push(poly.clone());
+            }
+        }
+
+        if num_triangulated > 0 {
+            self.bsp_data.collision_tree = BspData::recalculate(verts, polygons.into_iter());
+        }
+        num_triangulated
+    }
+
+    // rebuilds the normals of this subobject, smoothing across edges where the faces are within smoothing_angle (radians) of each other
+    // with hard_uv_seams set, a vertex is never smoothed with a face which disagrees on its uv, so uv seams become hard edges
+    pub fn recalc_normals(&mut self, smoothing_angle: f32, hard_uv_seams: bool) {
+        let verts = &self.bsp_data.verts;
+        let polygons = self.bsp_data.collision_tree.leaves().map(|(_, poly)| poly).collect::<Vec<_>>();
+
+        // newell normals are weighted by area, so big faces will pull harder on the smoothed normals
+        let face_normals = polygons.iter().map(|poly| poly.newell_normal(verts)).collect::<Vec<_>>();
+
+        // every polygon corner that touches each vertex, as (polygon index, corner index)
+        let mut vert_corners: HashMap<VertexId, Vec<(usize, usize)>> = HashMap::new();
+        for (i, poly) in polygons.iter().enumerate() {
+            for (j, polyvert) in poly.verts.iter().enumerate() {
+                vert_corners.entry(polyvert.vertex_id).or_default().push((i, j));
+            }
+        }
+
+        // a little tolerance, so that coplanar faces still get smoothed together in flat mode
+        let min_cos = smoothing_angle.cos() - 0.0001;
+        let mut norms = vec![];
+        let mut norms_map: HashMap<Vec3d, NormalId> = HashMap::new();
+
+        let normal_ids = polygons
+            .iter()
+            .enumerate()
+            .map(|(i, poly)| {
+                let face_normal = face_normals[i].normalize();
+                poly.verts
+                    .iter()
+                    .map(|polyvert| {
+                        let mut normal = Vec3d::ZERO;
+                        for &(other_i, other_j) in &vert_corners[&polyvert.vertex_id] {
+                            let other_normal = face_normals[other_i];
+                            let smooth = other_i == i
+                                || (face_normal.dot(other_normal.normalize()) >= min_cos
+                                    && (!hard_uv_seams || polygons[other_i].verts[other_j].uv == polyvert.uv));
+                            if smooth {
+                                normal += other_normal;
+                            }
+                        }
+                        let normal = if normal.is_null() {
+                            Vec3d::new(0.0, 0.0, 1.0)
+                        } else {
+                            normal.normalize()
+                        };
+
+                        *norms_map.entry(normal).or_insert_with(|| {
+                            norms.push(normal);
+                            NormalId(norms.len() as u32 - 1)
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // leaves() and leaves_mut() visit the polygons in the same order
+        let mut key_map = HashMap::new();
+        for ((_, poly), normal_ids) in self.bsp_data.collision_tree.leaves_mut().zip(normal_ids) {
+            for (polyvert, normal_id) in poly.verts.iter_mut().zip(normal_ids) {
+                let old_key = polyvert.key();
+                polyvert.normal_id = normal_id;
+                key_map.insert(old_key, polyvert.key());
+            }
+        }
+        self.bsp_data.norms = norms;
+        self.bsp_data.extras = self.bsp_data.extras.as_ref().and_then(|extras| extras.remap(&key_map));
+    }
+
+    // every polygon gets its own face normal
+    pub fn recalc_normals_flat(&mut self) {
+        self.recalc_normals(0.0, false)
+    }
+
+    pub fn triangulate_all(&mut self) -> usize {
+        self.triangulate_filtered(|_, _| true)
+    }
+
+    // non-planar polygons produce wrong collisions and shading in the engine, so this triangulates just those
+    pub fn split_non_planar_polygons(&mut self, tolerance: f32) -> usize {
+        self.triangulate_filtered(|poly, verts| !poly.is_planar(verts, tolerance))
+    }
+
+    // the convex hull of this subobject's geometry, a much cheaper stand-in for collision purposes
+    // hull vertices keep the texture and uv of some polygon which used them, normals are flat
+    pub fn generate_convex_hull(&self) -> Option<BspData> {
+        let hull = convex_hull(&self.bsp_data.verts)?;
+
+        let mut vert_info: HashMap<VertexId, (TextureId, (f32, f32))> = HashMap::new();
+        for (_, poly) in self.bsp_data.collision_tree.leaves() {
+            for polyvert in &poly.verts {
+                vert_info.entry(polyvert.vertex_id).or_insert((poly.texture, polyvert.uv));
+            }
+        }
+
+        let mut vert_map: HashMap<usize, VertexId> = HashMap::new();
+        let mut verts = vec![];
+        let mut norms = vec![];
+        let mut polygons = vec![];
+        for tri in hull {
+            let [a, b, c] = tri.map(|i| self.bsp_data.verts[i]);
+            let normal_id = NormalId(norms.len() as u32);
+            norms.push((b - a).cross(c - a).normalize());
+
+            let poly_verts = tri
+                .iter()
+                .map(|&i| PolyVertex {
+                    vertex_id: *vert_map.entry(i).or_insert_with(|| {
+                        verts.push(self.bsp_data.verts[i]);
+                        VertexId(verts.len() as u32 - 1)
+                    }),
+                    normal_id,
+                    uv: vert_info.get(&VertexId(i as u32)).map_or((0.0, 0.0), |info| info.1),
+                })
+                .collect();
+            let texture = vert_info.get(&VertexId(tri[0] as u32)).map_or(TextureId(0), |info| info.0);
+
+            polygons.push(Polygon { normal: Vec3d::ZERO, texture, verts: poly_verts });
+        }
+
+        Some(BspData {
+            collision_tree: BspData::recalculate(&verts, polygons.into_iter()),
+            verts,
+            norms,
+            extras: None,
+        })
+    }
+
+    // reduces this subobject to about ratio of its triangles, by repeatedly collapsing whichever edge changes the shape the least
+    // (quadric error metrics, Garland & Heckbert), edges along uv seams, texture boundaries and open borders are weighted heavily to stay in place
+    // vertices only ever collapse onto each other, so the surviving corners can keep existing uvs and normals
+    pub fn decimate(&mut self, ratio: f32) {
+        #[derive(Clone, Copy)]
+        struct Corner {
+            vert: usize,
+            normal_id: NormalId,
+            uv: (f32, f32),
+        }
+        struct Tri {
+            corners: [Corner; 3],
+            texture: TextureId,
+            alive: bool,
+        }
+        impl Tri {
+            fn has(&self, vert: usize) -> bool {
+                self.corners.iter().any(|corner| corner.vert == vert)
+            }
+            fn corner(&self, vert: usize) -> Corner {
+                *self.corners.iter().find(|corner| corner.vert == vert).unwrap()
+            }
+            fn normal(&self, verts: &[Vec3d]) -> Vec3d {
+                let [a, b, c] = self.corners.map(|corner| verts[corner.vert]);
+                (b - a).cross(c - a)
+            }
+        }
+
+        fn live_tris(tris: &[Tri], vert_tris: &[Vec<usize>], vert: usize) -> Vec<usize> {
+            vert_tris[vert].iter().copied().filter(|&i| tris[i].alive).collect()
+        }
+        fn neighbors(tris: &[Tri], vert_tris: &[Vec<usize>], vert: usize, excluding: [usize; 2]) -> HashSet<usize> {
+            live_tris(tris, vert_tris, vert)
+                .into_iter()
+                .flat_map(|i| tris[i].corners.map(|corner| corner.vert))
+                .filter(|other| !excluding.contains(other))
+                .collect()
+        }
+
+        const BOUNDARY_WEIGHT: f32 = 1000.0;
+
+        let verts = &self.bsp_data.verts;
+        let mut tris = self
+            .bsp_data
+            .collision_tree
+            .leaves()
+            .flat_map(|(_, poly)| poly.triangulate(verts))
+            .map(|tri| Tri {
+                corners: [0, 1, 2].map(|i| Corner {
+                    vert: tri.verts[i].vertex_id.0 as usize,
+                    normal_id: tri.verts[i].normal_id,
+                    uv: tri.verts[i].uv,
+                }),
+                texture: tri.texture,
+                alive: true,
+            })
+            .collect::<Vec<_>>();
+
+        let target = (tris.len() as f32 * ratio).round() as usize;
+        if target >= tris.len() {
+            return;
+        }
+
+        let plane_quadric = |normal: Vec3d, point: Vec3d, weight: f32| {
+            let plane = glm::DVec4::new(normal.x as f64, normal.y as f64, normal.z as f64, -normal.dot(point) as f64);
+            plane * plane.transpose() * weight as f64
+        };
+        let error = |quadric: &glm::DMat4, point: Vec3d| {
+            let point = glm::DVec4::new(point.x as f64, point.y as f64, point.z as f64, 1.0);
+            point.dot(&(quadric * point))
+        };
+
+        // each vertex starts with the squared distance to the planes of its triangles, weighted by their area
+        let mut quadrics = vec![glm::DMat4::zeros(); verts.len()];
+        let mut vert_tris = vec![vec![]; verts.len()];
+        let mut edge_tris: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, tri) in tris.iter().enumerate() {
+            let normal = tri.normal(verts);
+            if !normal.is_null() {
+                let quadric = plane_quadric(normal.normalize(), verts[tri.corners[0].vert], normal.magnitude() / 2.0);
+                for corner in &tri.corners {
+                    quadrics[corner.vert] += quadric;
+                }
+            }
+            for j in 0..3 {
+                let (a, b) = (tri.corners[j].vert, tri.corners[(j + 1) % 3].vert);
+                vert_tris[a].push(i);
+                edge_tris.entry((a.min(b), a.max(b))).or_default().push(i);
+            }
+        }
+
+        // edges which must keep their shape get planes perpendicular to their faces, so sliding along them is cheap but moving off is not
+        for (&(a, b), edge) in &edge_tris {
+            let first = &tris[edge[0]];
+            let is_boundary = edge.len() == 1
+                || edge.iter().any(|&i| {
+                    let tri = &tris[i];
+                    tri.texture != first.texture || tri.corner(a).uv != first.corner(a).uv || tri.corner(b).uv != first.corner(b).uv
+                });
+            if is_boundary {
+                let edge_vec = verts[b] - verts[a];
+                for &i in edge {
+                    let normal = edge_vec.cross(tris[i].normal(verts));
+                    if !normal.is_null() {
+                        let quadric = plane_quadric(normal.normalize(), verts[a], BOUNDARY_WEIGHT * edge_vec.dot(edge_vec));
+                        quadrics[a] += quadric;
+                        quadrics[b] += quadric;
+                    }
+                }
+            }
+        }
+
+        // a min-heap of collapses, costs are never negative so their bits sort the same way the floats do
+        // each remembers the versions of its vertices at the time, so it can be thrown out if either has changed since
+        let mut versions = vec![0_u32; verts.len()];
+        let mut heap = BinaryHeap::new();
+        let push_collapse = |heap: &mut BinaryHeap<_>, quadrics: &[glm::DMat4], versions: &[u32], a: usize, b: usize| {
+            let quadric = quadrics[a] + quadrics[b];
+            let (cost_to_a, cost_to_b) = (error(&quadric, verts[a]), error(&quadric, verts[b]));
+            let (cost, from, to) = if cost_to_a < cost_to_b { (cost_to_a, b, a) } else { (cost_to_b, a, b) };
+            heap.push(Reverse((cost.max(0.0).to_bits(), from, to, versions[from], versions[to])));
+        };
+        for &(a, b) in edge_tris.keys() {
+            push_collapse(&mut heap, &quadrics, &versions, a, b);
+        }
+
+        let mut num_tris = tris.len();
+        while num_tris > target {
+            let (from, to) = match heap.pop() {
+                Some(Reverse((_, from, to, from_version, to_version))) if versions[from] == from_version && versions[to] == to_version => (from, to),
+                Some(_) => continue,
+                None => break,
+            };
+
+            let (shared_tris, moved_tris): (Vec<_>, Vec<_>) = live_tris(&tris, &vert_tris, from).into_iter().partition(|&i| tris[i].has(to));
+            if shared_tris.is_empty() {
+                continue;
+            }
+
+            // the two vertices can't share any neighbors besides the ones across the collapsing triangles, or the surface will fold onto itself
+            let from_neighbors = neighbors(&tris, &vert_tris, from, [from, to]);
+            if from_neighbors.intersection(&neighbors(&tris, &vert_tris, to, [from, to])).count() > shared_tris.len() {
+                continue;
+            }
+
+            // and none of the remaining triangles can be flipped over or squashed flat
+            let flips = moved_tris.iter().any(|&i| {
+                let mut new_tri = tris[i].corners;
+                new_tri
+                    .iter_mut()
+                    .filter(|corner| corner.vert == from)
+                    .for_each(|corner| corner.vert = to);
+                let [a, b, c] = new_tri.map(|corner| verts[corner.vert]);
+                let new_normal = (b - a).cross(c - a);
+                new_normal.is_null() || new_normal.dot(tris[i].normal(verts)) <= 0.0
+            });
+            if flips {
+                continue;
+            }
+
+            for &i in &shared_tris {
+                tris[i].alive = false;
+                num_tris -= 1;
+            }
+            for &i in &moved_tris {
+                for j in 0..3 {
+                    let corner = tris[i].corners[j];
+                    if corner.vert != from {
+                        continue;
+                    }
+                    // take the uv and normal from a collapsed triangle on the same side of any seam as this corner, if there is one
+                    let replacement = shared_tris
+                        .iter()
+                        .map(|&k| (tris[k].corner(from), tris[k].corner(to)))
+                        .find(|(old, _)| old.uv == corner.uv && old.normal_id == corner.normal_id)
+                        .map_or(corner, |(_, new)| new);
+                    tris[i].corners[j] = Corner { vert: to, ..replacement };
+                }
+                vert_tris[to].push(i);
+            }
+
+            quadrics[to] = quadrics[to] + quadrics[from];
+            versions[from] += 1;
+            versions[to] += 1;
+            for other in neighbors(&tris, &vert_tris, to, [to, to]) {
+                push_collapse(&mut heap, &quadrics, &versions, other, to);
+            }
+        }
+
+        let polygons = tris.into_iter().filter(|tri| tri.alive).map(|tri| Polygon {
+            normal: Vec3d::ZERO,
+            texture: tri.texture,
+            verts: tri
+                .corners
+                .iter()
+                .map(|corner| PolyVertex {
+                    vertex_id: VertexId(corner.vert as u32),
+                    normal_id: corner.normal_id,
+                    uv: corner.uv,
+                })
+                .collect(),
+        });
+        self.bsp_data = self.bsp_data.extract_polygons(polygons);
+    }
+}
+impl Serialize for SubObject {
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let version = get_version();
+        self.obj_id.write_to(w)?;
+        if version >= Version::V21_16 {
+            self.radius.write_to(w)?;
+            self.parent.unwrap_or(ObjectId(u32::MAX)).write_to(w)?;
+            self.offset.write_to(w)?;
         } else {
             self.parent.unwrap_or(ObjectId(u32::MAX)).write_to(w)?;
             self.offset.write_to(w)?;
@@ -1485,6 +2729,22 @@ mk_versions! {
     V23_00(2300, "23.00"),
 }
 
+// how the center of mass is approximated from the geometry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CenterOfMassMode {
+    // every vertex is an equal point mass
+    Vertex,
+    // mass is spread evenly over the surface
+    Area,
+    // mass is spread evenly over the enclosed volume, only possible with closed meshes; falls back to area otherwise
+    Volume,
+}
+impl Default for CenterOfMassMode {
+    fn default() -> Self {
+        Self::Volume
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Model {
     pub version: Version,
@@ -1545,6 +2805,16 @@ impl Model {
         None
     }
 
+    // textures are looked for next to the model, or in the usual maps folder beside it
+    pub fn find_texture_file(&self, name: &str) -> Option<PathBuf> {
+        TEXTURE_EXTENSIONS.iter().find_map(|ext| {
+            [format!("{}.{}", name, ext), format!("../maps/{}.{}", name, ext)]
+                .into_iter()
+                .map(|file_name| self.path_to_file.with_file_name(file_name))
+                .find(|path| path.is_file())
+        })
+    }
+
     pub fn get_subobj_names(&self) -> Vec<String> {
         let mut ret = vec![];
         for subobj in &self.sub_objects {
@@ -1574,6 +2844,93 @@ impl Model {
         }
     }
 
+    // removes a texture, shifting down the ids of the textures after it
+    // any polygons which used it are moved to the untextured slot (which is created if there isn't one already)
+    pub fn remove_texture(&mut self, id: TextureId) -> String {
+        let removed = self.textures.remove(id.0 as usize);
+
+        let mut orphaned_polys = false;
+        for subobj in self.sub_objects.iter_mut() {
+            for (_, poly) in subobj.bsp_data.collision_tree.leaves_mut() {
+                if poly.texture == id {
+                    poly.texture = TextureId::UNTEXTURED;
+                    orphaned_polys = true;
+                } else if poly.texture > id {
+                    poly.texture.0 -= 1;
+                }
+            }
+        }
+
+        self.untextured_idx = match self.untextured_idx {
+            Some(untextured_idx) if untextured_idx == id => None,
+            Some(untextured_idx) if untextured_idx > id => Some(TextureId(untextured_idx.0 - 1)),
+            untextured_idx => untextured_idx,
+        };
+        let untextured_idx = post_parse_fill_untextured_slot(&mut self.sub_objects, &mut self.textures);
+        if orphaned_polys {
+            self.untextured_idx = untextured_idx;
+        }
+
+        removed
+    }
+
+    // removes a path, fixing up docking bays which use it, and removing it from any special point or docking bay properties which name it
+    pub fn remove_path(&mut self, id: PathId) -> Path {
+        self.path_removal_fixup(id);
+        let removed = self.paths.remove(id.0 as usize);
+
+        let properties = self.special_points.iter_mut().map(|point| &mut point.properties);
+        for properties in properties.chain(self.docking_bays.iter_mut().map(|bay| &mut bay.properties)) {
+            if properties_get_field(properties, "$path") == Some(&removed.name) {
+                properties_update_field(properties, "$path", "");
+            }
+        }
+
+        removed
+    }
+
+    // removes a turret, and takes its base subobject out of any path point turret lists if no other turret uses it
+    pub fn remove_turret(&mut self, idx: usize) -> Turret {
+        let removed = self.turrets.remove(idx);
+
+        if !self.turrets.iter().any(|turret| turret.base_obj == removed.base_obj) {
+            for path in &mut self.paths {
+                for point in &mut path.points {
+                    point.turrets.retain(|&turret| turret != removed.base_obj);
+                }
+            }
+        }
+
+        removed
+    }
+
+    pub fn remove_glow_bank(&mut self, idx: usize) -> GlowPointBank {
+        self.glow_banks.remove(idx)
+    }
+
+    pub fn remove_weapon_bank(&mut self, is_primary: bool, idx: usize) -> Vec<WeaponHardpoint> {
+        if is_primary {
+            self.primary_weps.remove(idx)
+        } else {
+            self.secondary_weps.remove(idx)
+        }
+    }
+
+    // removes a special point, and detaches any paths belonging to it (unless a subobject has the same name)
+    pub fn remove_special_point(&mut self, idx: usize) -> SpecialPoint {
+        let removed = self.special_points.remove(idx);
+
+        if self.get_obj_id_by_name(&removed.name).is_none() {
+            for path in &mut self.paths {
+                if path.parent == removed.name {
+                    path.parent.clear();
+                }
+            }
+        }
+
+        removed
+    }
+
     pub fn get_valid_gun_subobjects_for_turret(&self, existing_obj: ObjectId, turret_obj: ObjectId) -> (Vec<ObjectId>, usize) {
         let mut out_vec = vec![];
         let mut out_idx = 0;
@@ -1726,35 +3083,221 @@ impl Model {
         self.header.bbox = new_bbox;
     }
 
+    // every subobject in the detail0 hierarchy, along with its total offset
+    fn detail0_subobjects(&self) -> Vec<(&SubObject, Vec3d)> {
+        match self.header.detail_levels.first() {
+            Some(&detail_0) => self
+                .sub_objects
+                .iter()
+                .filter(|subobj| self.is_obj_id_ancestor(subobj.obj_id, detail_0))
+                .map(|subobj| (subobj, self.get_total_subobj_offset(subobj.obj_id)))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // integrates over the volume enclosed by the detail0 hierarchy using the divergence theorem, summing up signed tetrahedrons
+    // formed by each triangle and the origin, see "Polyhedral Mass Properties (Revisited)" by David Eberly
+    // returns the volume, the center of volume, and the inertia tensor about that center (for a density of 1)
+    // or None if the meshes aren't closed, in which case there isn't any volume to speak of
+    pub fn volume_integrals(&self) -> Option<(f32, Vec3d, Mat3x3)> {
+        let subobjects = self.detail0_subobjects();
+        if subobjects.is_empty() || !subobjects.iter().all(|(subobj, _)| subobj.bsp_data.is_closed()) {
+            return None;
+        }
+
+        let mut volume = 0.0;
+        let mut first_moment = Vec3::zeros();
+        let mut second_moment = Mat3x3::zeros();
+
+        for (subobj, offset) in subobjects {
+            let verts = &subobj.bsp_data.verts;
+            for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
+                // fan triangulation is fine here even for concave polygons, the signed volumes cancel out correctly
+                let a = Vec3::from(verts[poly.verts[0].vertex_id.0 as usize] + offset);
+                for pair in poly.verts[1..].windows(2) {
+                    let b = Vec3::from(verts[pair[0].vertex_id.0 as usize] + offset);
+                    let c = Vec3::from(verts[pair[1].vertex_id.0 as usize] + offset);
+
+                    let tet_volume = a.dot(&b.cross(&c)) / 6.0;
+                    let sum = a + b + c;
+                    volume += tet_volume;
+                    first_moment += sum * (tet_volume / 4.0);
+                    second_moment += (a * a.transpose() + b * b.transpose() + c * c.transpose() + sum * sum.transpose()) * (tet_volume / 20.0);
+                }
+            }
+        }
+
+        if volume.abs() < 0.000001 {
+            return None;
+        }
+
+        // inside-out meshes just give the negative of everything
+        if volume < 0.0 {
+            volume = -volume;
+            first_moment = -first_moment;
+            second_moment = -second_moment;
+        }
+
+        let center = first_moment / volume;
+        // shift to be about the center, by the parallel axis theorem
+        let second_moment = second_moment - center * center.transpose() * volume;
+        let inertia = Mat3x3::identity() * second_moment.trace() - second_moment;
+
+        Some((volume, center.into(), inertia))
+    }
+
+    // calculates the mass, center of mass, and moment of inertia as a solid of uniform density enclosed by the detail0 meshes
+    // if the meshes aren't closed this falls back to the usual estimates, returns whether the volume could be used
+    pub fn recalc_mass_properties_from_volume(&mut self, density: f32) -> bool {
+        if let Some((volume, center, inertia)) = self.volume_integrals() {
+            self.header.mass = volume * density;
+            self.header.center_of_mass = center;
+            self.header.moment_of_inertia = (inertia * density).try_inverse().unwrap_or_else(Mat3x3::zeros).into();
+            true
+        } else {
+            self.recalc_mass();
+            self.recalc_center_of_mass(CenterOfMassMode::Area);
+            self.recalc_moi();
+            false
+        }
+    }
+
     pub fn recalc_mass(&mut self) {
         self.header.mass = 4.65 * (self.header.bbox.volume().powf(2.0 / 3.0));
     }
 
-    pub fn recalc_moi(&mut self) {
-        self.header.moment_of_inertia = Mat3d::default();
+    pub fn recalc_center_of_mass(&mut self, mode: CenterOfMassMode) {
+        let subobjects = self.detail0_subobjects();
+
+        self.header.center_of_mass = match mode {
+            CenterOfMassMode::Vertex => Vec3d::average(
+                subobjects
+                    .iter()
+                    .flat_map(|(subobj, offset)| subobj.bsp_data.verts.iter().map(move |vert| *vert + *offset)),
+            ),
+            CenterOfMassMode::Area => {
+                // the centroids of every triangle, weighted by their area
+                let mut total_area = 0.0;
+                let mut center = Vec3d::ZERO;
+                for (subobj, offset) in &subobjects {
+                    let verts = &subobj.bsp_data.verts;
+                    for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
+                        let a = verts[poly.verts[0].vertex_id.0 as usize] + *offset;
+                        for pair in poly.verts[1..].windows(2) {
+                            let b = verts[pair[0].vertex_id.0 as usize] + *offset;
+                            let c = verts[pair[1].vertex_id.0 as usize] + *offset;
+                            let area = (b - a).cross(c - a).magnitude() / 2.0;
+                            total_area += area;
+                            center += (a + b + c) * (area / 3.0);
+                        }
+                    }
+                }
+                if total_area > 0.0 {
+                    center / total_area
+                } else {
+                    Vec3d::ZERO
+                }
+            }
+            CenterOfMassMode::Volume => match self.volume_integrals() {
+                Some((_, center, _)) => center,
+                None => return self.recalc_center_of_mass(CenterOfMassMode::Area),
+            },
+        };
 
-        fn sum_verts_recurse(subobjects: &ObjVec<SubObject>, id: ObjectId) -> usize {
-            subobjects[id].bsp_data.verts.len() + subobjects[id].children.iter().map(|id| sum_verts_recurse(subobjects, *id)).sum::<usize>()
+        if self.header.center_of_mass.x.is_nan() {
+            self.header.center_of_mass = Vec3d::ZERO;
         }
+    }
 
-        if let Some(&detail_0) = self.header.detail_levels.first() {
-            let num_verts = sum_verts_recurse(&self.sub_objects, detail_0);
+    // treats every vertex as an equal point mass, and sums up their inertia about the center of mass
+    pub fn recalc_moi(&mut self) {
+        self.header.moment_of_inertia = Mat3d::default();
+
+        let subobjects = self.detail0_subobjects();
+        if !subobjects.is_empty() {
+            let num_verts = subobjects.iter().map(|(subobj, _)| subobj.bsp_data.verts.len()).sum::<usize>();
 
             let point_mass = self.header.mass / num_verts as f32;
 
-            fn accumulate_moi_recurse(subobjects: &ObjVec<SubObject>, id: ObjectId, moi: &mut Mat3d) {
-                subobjects[id].bsp_data.verts.iter().for_each(|vert| moi.add_point_mass_moi(*vert));
-                subobjects[id].children.iter().for_each(|id| accumulate_moi_recurse(subobjects, *id, moi));
+            let mut moi = Mat3d::default();
+            for (subobj, offset) in subobjects {
+                for vert in &subobj.bsp_data.verts {
+                    moi.add_point_mass_moi(*vert + offset - self.header.center_of_mass);
+                }
             }
 
-            accumulate_moi_recurse(&self.sub_objects, detail_0, &mut self.header.moment_of_inertia);
-
-            let mut glm_mat: Mat3x3 = self.header.moment_of_inertia.into();
+            let mut glm_mat: Mat3x3 = moi.into();
             glm_mat *= point_mass;
             self.header.moment_of_inertia = glm_mat.try_inverse().unwrap().into();
         }
     }
 
+    // slices the detail0 hierarchy along the z axis into slabs of equal depth, recording for each the depth of its center,
+    // and the greatest distance from the z axis reached by any geometry within it
+    pub fn recalc_cross_sections(&mut self, num_sections: usize) {
+        self.header.cross_sections.clear();
+
+        let subobjects = self.detail0_subobjects();
+        let polygons = subobjects
+            .iter()
+            .flat_map(|(subobj, offset)| {
+                let verts = &subobj.bsp_data.verts;
+                subobj.bsp_data.collision_tree.leaves().map(move |(_, poly)| {
+                    poly.verts
+                        .iter()
+                        .map(|polyvert| verts[polyvert.vertex_id.0 as usize] + *offset)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if num_sections == 0 || polygons.is_empty() {
+            return;
+        }
+
+        let bbox = BoundingBox::from_vectors(polygons.iter().flatten().copied());
+        let slab_depth = (bbox.z_length() / num_sections as f32).max(f32::EPSILON);
+
+        // sutherland-hodgman, against a single plane perpendicular to z, keeping whichever side 'keep_above' says
+        fn clip(points: &[Vec3d], z: f32, keep_above: bool) -> Vec<Vec3d> {
+            let inside = |point: &Vec3d| (point.z >= z) == keep_above;
+            let mut out = vec![];
+            for (i, &cur) in points.iter().enumerate() {
+                let next = points[(i + 1) % points.len()];
+                if inside(&cur) {
+                    out.push(cur);
+                }
+                if inside(&cur) != inside(&next) {
+                    let t = (z - cur.z) / (next.z - cur.z);
+                    out.push(cur + (next - cur) * t);
+                }
+            }
+            out
+        }
+
+        let mut radii = vec![0.0_f32; num_sections];
+        for points in &polygons {
+            let slab_of = |z: f32| (((z - bbox.min.z) / slab_depth) as usize).min(num_sections - 1);
+            let first = slab_of(points.iter().map(|point| point.z).fold(f32::INFINITY, f32::min));
+            let last = slab_of(points.iter().map(|point| point.z).fold(f32::NEG_INFINITY, f32::max));
+
+            for (slab, radius) in radii.iter_mut().enumerate().take(last + 1).skip(first) {
+                let slab_min = bbox.min.z + slab as f32 * slab_depth;
+                let clipped = clip(&clip(points, slab_min, true), slab_min + slab_depth, false);
+                for point in clipped {
+                    *radius = radius.max(f32::hypot(point.x, point.y));
+                }
+            }
+        }
+
+        self.header.cross_sections = radii
+            .into_iter()
+            .enumerate()
+            .map(|(slab, radius)| (bbox.min.z + (slab as f32 + 0.5) * slab_depth, radius))
+            .collect();
+    }
+
     pub fn clean_up(&mut self) {
         if let Some(shield) = &mut self.shield_data {
             if shield.collision_tree.is_none() {
@@ -1781,6 +3324,374 @@ impl Model {
         }
     }
 
+    // appends a subobject to the model, optionally as a child of parent, returning its new id
+    // any hierarchy links it already had are discarded
+    pub fn add_subobject(&mut self, mut subobj: SubObject, parent: Option<ObjectId>) -> ObjectId {
+        let id = ObjectId(self.sub_objects.len() as u32);
+        subobj.obj_id = id;
+        subobj.parent = None;
+        subobj.children.clear();
+        self.sub_objects.push(subobj);
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+
+        if let Some(parent) = parent {
+            self.make_parent(parent, id);
+        }
+        id
+    }
+
+    // removes a subobject along with all of its descendants, renumbering everything after them
+    // turrets on any of them are removed, and anything else attached to them is moved to the parent if there is one, or removed otherwise
+    // if it was a detail level, the levels after it (and the insignia and glow banks on those levels) move up one
+    // returns the ids (as they were before removal) of every subobject removed, in ascending order
+    pub fn remove_subobject(&mut self, id: ObjectId) -> Vec<ObjectId> {
+        let mut removed = vec![id];
+        let mut i = 0;
+        while i < removed.len() {
+            removed.extend(self.sub_objects[removed[i]].children.iter().copied());
+            i += 1;
+        }
+        removed.sort();
+
+        self.turrets
+            .retain(|turret| !removed.contains(&turret.base_obj) && !removed.contains(&turret.gun_obj));
+        for path in &mut self.paths {
+            for point in &mut path.points {
+                point.turrets.retain(|turret| !removed.contains(turret));
+            }
+        }
+
+        if let Some(parent) = self.sub_objects[id].parent {
+            for &removed_id in &removed {
+                self.redirect_subobj_references(removed_id, parent);
+            }
+        } else {
+            let removed_names = removed
+                .iter()
+                .map(|&removed_id| self.sub_objects[removed_id].name.clone())
+                .collect::<Vec<_>>();
+            self.eye_points.retain(|eye_point| !removed.contains(&eye_point.attached_subobj));
+            self.glow_banks.retain(|glow_bank| !removed.contains(&glow_bank.obj_parent));
+            for path in &mut self.paths {
+                if removed_names.contains(&path.parent) {
+                    path.parent.clear();
+                }
+            }
+            for dock in &mut self.docking_bays {
+                if properties_get_field(&dock.properties, "$parent_submodel")
+                    .map_or(false, |name| removed_names.iter().any(|removed| removed == name))
+                {
+                    properties_update_field(&mut dock.properties, "$parent_submodel", "");
+                }
+            }
+        }
+
+        if let Some(level) = self.header.detail_levels.iter().position(|&detail_id| detail_id == id) {
+            let level = level as u32;
+            self.header.detail_levels.remove(level as usize);
+            self.insignias.retain(|insignia| insignia.detail_level != level);
+            for insignia in &mut self.insignias {
+                if insignia.detail_level > level {
+                    insignia.detail_level -= 1;
+                }
+            }
+            self.glow_banks.retain(|glow_bank| glow_bank.lod != level);
+            for glow_bank in &mut self.glow_banks {
+                if glow_bank.lod > level {
+                    glow_bank.lod -= 1;
+                }
+            }
+        }
+
+        self.make_orphan(id);
+        // highest first, so the ids still to be removed stay valid
+        for &removed_id in removed.iter().rev() {
+            self.remove_detached_subobject(removed_id);
+        }
+
+        removed
+    }
+
+    // points every reference to one subobject at another instead, moving anything positioned relative to it so it stays in place
+    fn redirect_subobj_references(&mut self, from: ObjectId, to: ObjectId) {
+        let shift = self.get_total_subobj_offset(from) - self.get_total_subobj_offset(to);
+        let (from_name, to_name) = (self.sub_objects[from].name.clone(), self.sub_objects[to].name.clone());
+
+        for turret in &mut self.turrets {
+            if turret.gun_obj == from {
+                turret.gun_obj = to;
+                for fire_point in &mut turret.fire_points {
+                    *fire_point += shift;
+                }
+            }
+            if turret.base_obj == from {
+                turret.base_obj = to;
+            }
+        }
+        for eye_point in &mut self.eye_points {
+            if eye_point.attached_subobj == from {
+                eye_point.attached_subobj = to;
+            }
+        }
+        for glow_bank in &mut self.glow_banks {
+            if glow_bank.obj_parent == from {
+                glow_bank.obj_parent = to;
+            }
+        }
+        for path in &mut self.paths {
+            if path.parent == from_name {
+                path.parent = to_name.clone();
+            }
+            for point in &mut path.points {
+                if point.turrets.contains(&to) {
+                    point.turrets.retain(|&turret| turret != from);
+                } else {
+                    for turret in &mut point.turrets {
+                        if *turret == from {
+                            *turret = to;
+                        }
+                    }
+                }
+            }
+        }
+        for dock in &mut self.docking_bays {
+            if properties_get_field(&dock.properties, "$parent_submodel") == Some(&from_name) {
+                properties_update_field(&mut dock.properties, "$parent_submodel", &to_name);
+                dock.position += shift;
+            }
+        }
+    }
+
+    // takes out a subobject which has already been detached from the hierarchy and which nothing refers to anymore,
+    // shifting down the ids of all the subobjects after it
+    fn remove_detached_subobject(&mut self, id: ObjectId) {
+        self.sub_objects.remove(id.0 as usize);
+
+        let fix_id = |obj_id: &mut ObjectId| {
+            if *obj_id > id {
+                obj_id.0 -= 1;
+            }
+        };
+        for subobj in self.sub_objects.iter_mut() {
+            fix_id(&mut subobj.obj_id);
+            if let Some(parent) = &mut subobj.parent {
+                fix_id(parent);
+            }
+            subobj.children.iter_mut().for_each(fix_id);
+        }
+        self.header.detail_levels.iter_mut().for_each(fix_id);
+        for turret in &mut self.turrets {
+            fix_id(&mut turret.base_obj);
+            fix_id(&mut turret.gun_obj);
+        }
+        for eye_point in &mut self.eye_points {
+            fix_id(&mut eye_point.attached_subobj);
+        }
+        for glow_bank in &mut self.glow_banks {
+            fix_id(&mut glow_bank.obj_parent);
+        }
+        for path in &mut self.paths {
+            for point in &mut path.points {
+                point.turrets.iter_mut().for_each(fix_id);
+            }
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+    }
+
+    // moves all of from's geometry and children into into, and redirects everything that referred to from, and then removes from
+    // detail level roots can't be merged away, and neither can an ancestor of into
+    // returns the (possibly shifted) id of the merged subobject
+    pub fn merge_subobjects(&mut self, into: ObjectId, from: ObjectId) -> Option<ObjectId> {
+        if into == from || self.is_obj_id_ancestor(into, from) || self.header.detail_levels.contains(&from) {
+            return None;
+        }
+
+        let shift = self.get_total_subobj_offset(from) - self.get_total_subobj_offset(into);
+
+        for child in self.sub_objects[from].children.clone() {
+            self.make_orphan(child);
+            self.sub_objects[child].offset += shift;
+            self.make_parent(into, child);
+        }
+
+        let from_bsp = std::mem::take(&mut self.sub_objects[from].bsp_data);
+        let into_bsp = &self.sub_objects[into].bsp_data;
+        let (num_verts, num_norms) = (into_bsp.verts.len() as u32, into_bsp.norms.len() as u32);
+        let mut from_key_map = HashMap::new();
+        let polygons = into_bsp
+            .collision_tree
+            .leaves()
+            .map(|(_, poly)| poly.clone())
+            .chain(from_bsp.collision_tree.leaves().map(|(_, poly)| {
+                let mut poly = poly.clone();
+                for polyvert in &mut poly.verts {
+                    let old_key = polyvert.key();
+                    polyvert.vertex_id.0 += num_verts;
+                    polyvert.normal_id.0 += num_norms;
+                    from_key_map.insert(old_key, polyvert.key());
+                }
+                poly
+            }))
+            .collect::<Vec<_>>();
+        let from_extras = from_bsp.extras.as_ref().and_then(|extras| extras.remap(&from_key_map));
+        let extras = match (into_bsp.extras.clone(), from_extras) {
+            (Some(mut extras), Some(from_extras)) => {
+                if extras.uv_sets.len() < from_extras.uv_sets.len() {
+                    extras.uv_sets.resize_with(from_extras.uv_sets.len(), Default::default);
+                }
+                for (set, from_set) in extras.uv_sets.iter_mut().zip(from_extras.uv_sets) {
+                    set.extend(from_set);
+                }
+                extras.colors.extend(from_extras.colors);
+                Some(extras)
+            }
+            (extras, from_extras) => extras.or(from_extras),
+        };
+        let verts = into_bsp
+            .verts
+            .iter()
+            .copied()
+            .chain(from_bsp.verts.iter().map(|&vert| vert + shift))
+            .collect::<Vec<_>>();
+        let norms = into_bsp.norms.iter().chain(&from_bsp.norms).copied().collect();
+
+        let into_subobj = &mut self.sub_objects[into];
+        into_subobj.bsp_data = BspData {
+            collision_tree: BspData::recalculate(&verts, polygons.into_iter()),
+            verts,
+            norms,
+            extras,
+        };
+        if !into_subobj.bsp_data.verts.is_empty() {
+            into_subobj.recalc_bbox();
+            into_subobj.recalc_radius();
+        }
+
+        self.redirect_subobj_references(from, into);
+        self.make_orphan(from);
+        self.remove_detached_subobject(from);
+
+        Some(if into > from { ObjectId(into.0 - 1) } else { into })
+    }
+
+    // the first group of polygons stays with the subobject, and each of the rest are moved to a new child of it,
+    // whose offset is at the center of its polygons
+    fn split_subobject(&mut self, id: ObjectId, groups: Vec<(String, Vec<Polygon>)>) -> Vec<ObjectId> {
+        if groups.len() <= 1 {
+            return vec![];
+        }
+
+        let bsp_data = std::mem::take(&mut self.sub_objects[id].bsp_data);
+        let mut groups = groups
+            .into_iter()
+            .map(|(name, polygons)| (name, bsp_data.extract_polygons(polygons.into_iter())));
+
+        let subobj = &mut self.sub_objects[id];
+        subobj.bsp_data = groups.next().unwrap().1;
+        subobj.recalc_bbox();
+        subobj.recalc_radius();
+
+        let mut new_ids = vec![];
+        for (name, mut bsp_data) in groups {
+            let bbox = BoundingBox::from_vectors(bsp_data.verts.iter().copied());
+            let center = (bbox.min + bbox.max) / 2.0;
+            for vert in &mut bsp_data.verts {
+                *vert -= center;
+            }
+            bsp_data.collision_tree.recalculate_bboxes(&bsp_data.verts);
+
+            let new_id = ObjectId(self.sub_objects.len() as u32);
+            let mut new_subobj = SubObject {
+                obj_id: new_id,
+                radius: Default::default(),
+                parent: Some(id),
+                offset: center,
+                geo_center: Vec3d::ZERO,
+                bbox: Default::default(),
+                name,
+                properties: Default::default(),
+                movement_type: Default::default(),
+                movement_axis: Default::default(),
+                bsp_data,
+                children: Default::default(),
+                is_debris_model: false,
+            };
+            new_subobj.recalc_bbox();
+            new_subobj.recalc_radius();
+
+            self.sub_objects.push(new_subobj);
+            self.sub_objects[id].children.push(new_id);
+            new_ids.push(new_id);
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+        new_ids
+    }
+
+    // gives each texture used by a subobject (besides the most used one) its own child subobject
+    // returns the ids of the new subobjects
+    pub fn split_subobject_by_texture(&mut self, id: ObjectId) -> Vec<ObjectId> {
+        let mut groups: Vec<(TextureId, Vec<Polygon>)> = vec![];
+        for (_, poly) in self.sub_objects[id].bsp_data.collision_tree.leaves() {
+            match groups.iter_mut().find(|(texture, _)| *texture == poly.texture) {
+                Some((_, polygons)) => polygons.push(poly.clone()),
+                None => groups.push((poly.texture, vec![poly.clone()])),
+            }
+        }
+        groups.sort_by_key(|(_, polygons)| Reverse(polygons.len()));
+
+        let name = &self.sub_objects[id].name;
+        let groups = groups
+            .into_iter()
+            .map(|(texture, polygons)| {
+                let texture_name = self.textures.get(texture.0 as usize).map_or("untextured", |texture| texture);
+                (format!("{}-{}", name, texture_name), polygons)
+            })
+            .collect();
+        self.split_subobject(id, groups)
+    }
+
+    // gives each separate connected piece of a subobject (besides the biggest one) its own child subobject
+    // polygons are connected if they share any vertex position
+    // returns the ids of the new subobjects
+    pub fn separate_loose_parts(&mut self, id: ObjectId) -> Vec<ObjectId> {
+        let bsp_data = &self.sub_objects[id].bsp_data;
+        let polygons = bsp_data.collision_tree.leaves().map(|(_, poly)| poly).collect::<Vec<_>>();
+
+        // union-find over the polygons, joining each one to the first polygon seen at each of its vertices
+        fn find(parents: &mut [usize], i: usize) -> usize {
+            if parents[i] != i {
+                parents[i] = find(parents, parents[i]);
+            }
+            parents[i]
+        }
+        let mut parents = (0..polygons.len()).collect::<Vec<_>>();
+        let mut first_at_pos: HashMap<Vec3d, usize> = HashMap::new();
+        for (i, poly) in polygons.iter().enumerate() {
+            for polyvert in &poly.verts {
+                let other = *first_at_pos.entry(bsp_data.verts[polyvert.vertex_id.0 as usize]).or_insert(i);
+                let (root1, root2) = (find(&mut parents, i), find(&mut parents, other));
+                parents[root1] = root2;
+            }
+        }
+
+        let mut parts: HashMap<usize, Vec<Polygon>> = HashMap::new();
+        for (i, poly) in polygons.iter().enumerate() {
+            parts.entry(find(&mut parents, i)).or_default().push((*poly).clone());
+        }
+        let mut parts = parts.into_iter().collect::<Vec<_>>();
+        parts.sort_by_key(|(root, polygons)| (Reverse(polygons.len()), *root));
+
+        let name = &self.sub_objects[id].name;
+        let groups = parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, polygons))| (format!("{}-part{}", name, i), polygons))
+            .collect();
+        self.split_subobject(id, groups)
+    }
+
     pub fn max_verts_norms_per_subobj(&self) -> usize {
         if self.version >= Version::V23_00 {
             u32::MAX as usize
@@ -1788,6 +3699,587 @@ impl Model {
             u16::MAX as usize
         }
     }
+
+    // splits up any subobject with more vertices or normals than the current version allows, partitioning its polygons spatially
+    // the original subobject keeps the first chunk, the rest become children of it with no offset, so they stay in place and move along with it
+    // returns the ids of every subobject that was modified or created
+    pub fn split_oversized_subobjects(&mut self) -> Vec<ObjectId> {
+        let max = self.max_verts_norms_per_subobj();
+        let mut changed_ids = vec![];
+
+        // recursively halves the polygons along the greatest axis of their centers until each half fits
+        fn partition_recurse(mut polygons: Vec<(Vec3d, Polygon)>, max: usize, chunks: &mut Vec<Vec<Polygon>>) {
+            let num_verts = polygons
+                .iter()
+                .flat_map(|(_, poly)| poly.verts.iter().map(|vert| vert.vertex_id))
+                .collect::<HashSet<_>>()
+                .len();
+            let num_norms = polygons
+                .iter()
+                .flat_map(|(_, poly)| poly.verts.iter().map(|vert| vert.normal_id))
+                .collect::<HashSet<_>>()
+                .len();
+
+            if (num_verts <= max && num_norms <= max) || polygons.len() <= 1 {
+                chunks.push(polygons.into_iter().map(|(_, poly)| poly).collect());
+                return;
+            }
+
+            let axis = BoundingBox::from_vectors(polygons.iter().map(|(center, _)| *center)).greatest_dimension();
+            polygons.sort_by(|a, b| a.0[axis].partial_cmp(&b.0[axis]).unwrap());
+
+            let back = polygons.split_off(polygons.len() / 2);
+            partition_recurse(polygons, max, chunks);
+            partition_recurse(back, max, chunks);
+        }
+
+        for i in 0..self.sub_objects.len() {
+            let id = ObjectId(i as u32);
+            let bsp_data = &self.sub_objects[id].bsp_data;
+            if bsp_data.verts.len() <= max && bsp_data.norms.len() <= max {
+                continue;
+            }
+
+            let bsp_data = std::mem::take(&mut self.sub_objects[id].bsp_data);
+            let polygons = bsp_data
+                .collision_tree
+                .leaves()
+                .map(|(_, poly)| (Vec3d::average(poly.verts.iter().map(|vert| bsp_data.verts[vert.vertex_id.0 as usize])), poly.clone()))
+                .collect::<Vec<_>>();
+
+            let mut chunks = vec![];
+            partition_recurse(polygons, max, &mut chunks);
+            let mut chunks = chunks.into_iter().map(|polygons| bsp_data.extract_polygons(polygons.into_iter()));
+
+            let subobj = &mut self.sub_objects[id];
+            subobj.bsp_data = chunks.next().unwrap();
+            subobj.recalc_bbox();
+            subobj.recalc_radius();
+            changed_ids.push(id);
+
+            for (n, chunk) in chunks.enumerate() {
+                let new_id = ObjectId(self.sub_objects.len() as u32);
+                let mut new_subobj = SubObject {
+                    obj_id: new_id,
+                    radius: Default::default(),
+                    parent: Some(id),
+                    offset: Vec3d::ZERO,
+                    geo_center: Vec3d::ZERO,
+                    bbox: Default::default(),
+                    name: format!("{}-chunk{}", self.sub_objects[id].name, n + 1),
+                    properties: Default::default(),
+                    movement_type: Default::default(),
+                    movement_axis: Default::default(),
+                    bsp_data: chunk,
+                    children: Default::default(),
+                    is_debris_model: false,
+                };
+                new_subobj.recalc_bbox();
+                new_subobj.recalc_radius();
+
+                self.sub_objects.push(new_subobj);
+                self.sub_objects[id].children.push(new_id);
+                changed_ids.push(new_id);
+            }
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+        changed_ids
+    }
+
+    // gives a subobject an invisible convex hull child which takes over its collisions, the original is still rendered but no longer collided with
+    // returns the id of the new subobject, or None if the subobject is too flat to have a hull
+    pub fn add_collision_hull(&mut self, id: ObjectId) -> Option<ObjectId> {
+        let mut bsp_data = self.sub_objects[id].generate_convex_hull()?;
+
+        let invisible = match self.textures.iter().position(|tex| tex.eq_ignore_ascii_case("invisible")) {
+            Some(idx) => TextureId(idx as u32),
+            None => {
+                self.textures.push("invisible".to_string());
+                TextureId(self.textures.len() as u32 - 1)
+            }
+        };
+        for (_, poly) in bsp_data.collision_tree.leaves_mut() {
+            poly.texture = invisible;
+        }
+
+        let new_id = ObjectId(self.sub_objects.len() as u32);
+        let mut new_subobj = SubObject {
+            obj_id: new_id,
+            radius: Default::default(),
+            parent: Some(id),
+            offset: Vec3d::ZERO,
+            geo_center: Vec3d::ZERO,
+            bbox: Default::default(),
+            name: format!("{}-collision", self.sub_objects[id].name),
+            properties: "$collide_invisible".to_string(),
+            movement_type: Default::default(),
+            movement_axis: Default::default(),
+            bsp_data,
+            children: Default::default(),
+            is_debris_model: false,
+        };
+        new_subobj.recalc_bbox();
+        new_subobj.recalc_radius();
+
+        self.sub_objects.push(new_subobj);
+        let subobj = &mut self.sub_objects[id];
+        subobj.children.push(new_id);
+        if subobj.properties.is_empty() {
+            subobj.properties = "$nocollide_this_only".to_string();
+        } else {
+            properties_set_flag(&mut subobj.properties, "$nocollide_this_only");
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+        Some(new_id)
+    }
+
+    // appends a new detail level for each ratio, made of a copy of detail0 with each subobject decimated to that ratio of its triangles
+    // the copies are named per FSO's convention, the roots become "detailN" and the rest get the lod letter, like "turret01a" -> "turret01b"
+    // returns the ids of the new detail level roots
+    pub fn generate_lods(&mut self, ratios: &[f32]) -> Vec<ObjectId> {
+        let detail0 = match self.header.detail_levels.first() {
+            Some(&id) => id,
+            None => return vec![],
+        };
+
+        fn lod_name(name: &str, level: usize) -> String {
+            let letter = (b'a' + level as u8) as char;
+            match name.strip_suffix('a') {
+                Some(base) => format!("{}{}", base, letter),
+                None => format!("{}{}", name, letter),
+            }
+        }
+
+        fn copy_recursive(model: &mut Model, id: ObjectId, parent: Option<ObjectId>, level: usize, ratio: f32) -> ObjectId {
+            let new_id = ObjectId(model.sub_objects.len() as u32);
+            let subobj = &model.sub_objects[id];
+            let mut new_subobj = SubObject {
+                obj_id: new_id,
+                parent,
+                name: if parent.is_none() {
+                    format!("detail{}", level)
+                } else {
+                    lod_name(&subobj.name, level)
+                },
+                children: vec![],
+                is_debris_model: false,
+                ..subobj.clone()
+            };
+            new_subobj.decimate(ratio);
+            if !new_subobj.bsp_data.verts.is_empty() {
+                new_subobj.recalc_bbox();
+                new_subobj.recalc_radius();
+            }
+
+            model.sub_objects.push(new_subobj);
+            if let Some(parent) = parent {
+                model.sub_objects[parent].children.push(new_id);
+            }
+            for child in model.sub_objects[id].children.clone() {
+                copy_recursive(model, child, Some(new_id), level, ratio);
+            }
+            new_id
+        }
+
+        let mut new_roots = vec![];
+        for &ratio in ratios {
+            let level = self.header.detail_levels.len();
+            let root = copy_recursive(self, detail0, None, level, ratio);
+            self.header.detail_levels.push(root);
+            new_roots.push(root);
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+        new_roots
+    }
+
+    // breaks the detail0 hierarchy up along the cells of a voronoi diagram, and adds up to count of the pieces as debris subobjects
+    // the cells are seeded by spreading points out over the model's vertices, and then relaxing them with a few rounds of lloyd's algorithm
+    // the cut faces are capped with the most common texture of each piece, though if the model isn't closed it can't be capped cleanly,
+    // in which case the piece becomes the convex hull of what's inside its cell
+    // returns the ids of the new debris subobjects
+    pub fn generate_debris(&mut self, count: usize) -> Vec<ObjectId> {
+        const LLOYD_ITERATIONS: usize = 8;
+
+        #[derive(Clone, Copy)]
+        struct Corner {
+            pos: Vec3d,
+            normal: Vec3d,
+            uv: (f32, f32),
+        }
+        // a convex polygon, with no texture if it's part of a cap
+        #[derive(Clone)]
+        struct Fragment {
+            corners: Vec<Corner>,
+            texture: Option<TextureId>,
+        }
+
+        // sutherland-hodgman, keeping whatever is behind the plane
+        // also returns the edge the clipped polygon now has on the plane, reversed, since that's the edge a cap there will need
+        fn clip(corners: &[Corner], normal: Vec3d, dist: f32) -> (Vec<Corner>, Option<(Vec3d, Vec3d)>) {
+            let inside = |corner: &Corner| corner.pos.dot(normal) <= dist;
+            if corners.iter().all(inside) {
+                return (corners.to_vec(), None);
+            }
+
+            let mut out: Vec<Corner> = vec![];
+            let (mut exit, mut enter) = (None, None);
+            for (i, cur) in corners.iter().enumerate() {
+                let next = &corners[(i + 1) % corners.len()];
+                if inside(cur) {
+                    out.push(*cur);
+                }
+                if inside(cur) != inside(next) {
+                    // always interpolate in the same direction, so both polygons sharing this edge get exactly the same point
+                    let (a, b) = if cur.pos.to_tuple() < next.pos.to_tuple() {
+                        (cur, next)
+                    } else {
+                        (next, cur)
+                    };
+                    let t = (dist - a.pos.dot(normal)) / (b.pos.dot(normal) - a.pos.dot(normal));
+                    let point = Corner {
+                        pos: a.pos + (b.pos - a.pos) * t,
+                        normal: a.normal + (b.normal - a.normal) * t,
+                        uv: (a.uv.0 + (b.uv.0 - a.uv.0) * t, a.uv.1 + (b.uv.1 - a.uv.1) * t),
+                    };
+                    if out.last().map_or(true, |last| last.pos != point.pos) {
+                        out.push(point);
+                    }
+                    if inside(cur) {
+                        exit = Some(point.pos);
+                    } else {
+                        enter = Some(point.pos);
+                    }
+                }
+            }
+            if out.len() > 1 && out[0].pos == out[out.len() - 1].pos {
+                out.pop();
+            }
+
+            match (exit, enter) {
+                (Some(exit), Some(enter)) if exit != enter => (out, Some((enter, exit))),
+                _ => (out, None),
+            }
+        }
+
+        fn plane_basis(normal: Vec3d) -> (Vec3d, Vec3d) {
+            let up = if normal.y.abs() < 0.9 {
+                Vec3d::new(0.0, 1.0, 0.0)
+            } else {
+                Vec3d::new(1.0, 0.0, 0.0)
+            };
+            let u = up.cross(normal).normalize();
+            (u, normal.cross(u))
+        }
+
+        // chains the cap edges into loops and fills them in, holes (which wind backwards) get bridged into the loop around them
+        // returns None if the edges don't form clean loops
+        fn cap(edges: &[(Vec3d, Vec3d)], normal: Vec3d) -> Option<Vec<[Vec3d; 3]>> {
+            let mut next_point: HashMap<Vec3d, Vec3d> = HashMap::new();
+            for &(a, b) in edges {
+                if next_point.insert(a, b).is_some() {
+                    return None;
+                }
+            }
+
+            let (u, v) = plane_basis(normal);
+            let to_2d = |point: Vec3d| (point.dot(u), point.dot(v));
+            let area = |points: &[Vec3d]| {
+                (0..points.len())
+                    .map(|i| {
+                        let ((x1, y1), (x2, y2)) = (to_2d(points[i]), to_2d(points[(i + 1) % points.len()]));
+                        x1 * y2 - x2 * y1
+                    })
+                    .sum::<f32>()
+                    / 2.0
+            };
+            let contains = |points: &[Vec3d], point: Vec3d| {
+                let (x, y) = to_2d(point);
+                let mut inside = false;
+                for i in 0..points.len() {
+                    let ((x1, y1), (x2, y2)) = (to_2d(points[i]), to_2d(points[(i + 1) % points.len()]));
+                    if (y1 > y) != (y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            };
+
+            let mut outers = vec![];
+            let mut holes = vec![];
+            while let Some(&start) = next_point.keys().next() {
+                let mut points = vec![start];
+                let mut cur = next_point.remove(&start).unwrap();
+                while cur != start {
+                    points.push(cur);
+                    cur = next_point.remove(&cur)?;
+                }
+                if points.len() >= 3 {
+                    let area = area(&points);
+                    if area > 0.0 {
+                        outers.push((area, points));
+                    } else {
+                        holes.push(points);
+                    }
+                }
+            }
+
+            // bridge each hole to the nearest point of the smallest loop around it, rightmost holes first so the bridges don't cross
+            let rightmost = |points: &[Vec3d]| {
+                (0..points.len())
+                    .max_by(|&a, &b| to_2d(points[a]).0.partial_cmp(&to_2d(points[b]).0).unwrap())
+                    .unwrap()
+            };
+            holes.sort_by(|a, b| to_2d(b[rightmost(b)]).0.partial_cmp(&to_2d(a[rightmost(a)]).0).unwrap());
+            for hole in holes {
+                let outer = outers
+                    .iter_mut()
+                    .filter(|(_, outer)| contains(outer, hole[0]))
+                    .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+                if let Some((_, outer)) = outer {
+                    let hole_idx = rightmost(&hole);
+                    let outer_idx = (0..outer.len())
+                        .min_by(|&a, &b| {
+                            let dist = |i: usize| (outer[i] - hole[hole_idx]).magnitude();
+                            dist(a).partial_cmp(&dist(b)).unwrap()
+                        })
+                        .unwrap();
+                    let mut bridged = outer[..=outer_idx].to_vec();
+                    bridged.extend(hole[hole_idx..].iter().chain(&hole[..=hole_idx]));
+                    bridged.extend(&outer[outer_idx..]);
+                    *outer = bridged;
+                }
+            }
+
+            let mut tris = vec![];
+            for (_, points) in outers {
+                let poly = Polygon {
+                    normal,
+                    texture: TextureId(0),
+                    verts: (0..points.len())
+                        .map(|i| PolyVertex {
+                            vertex_id: VertexId(i as u32),
+                            normal_id: NormalId(0),
+                            uv: (0.0, 0.0),
+                        })
+                        .collect(),
+                };
+                tris.extend(
+                    poly.triangulate(&points)
+                        .into_iter()
+                        .map(|tri| [0, 1, 2].map(|i| points[tri.verts[i].vertex_id.0 as usize])),
+                );
+            }
+            Some(tris)
+        }
+
+        let mut fragments = vec![];
+        for (subobj, offset) in self.detail0_subobjects() {
+            if subobj.is_destroyed_model() {
+                continue;
+            }
+            let bsp_data = &subobj.bsp_data;
+            for (_, poly) in bsp_data.collision_tree.leaves() {
+                for tri in poly.triangulate(&bsp_data.verts) {
+                    fragments.push(Fragment {
+                        corners: tri
+                            .verts
+                            .iter()
+                            .map(|polyvert| Corner {
+                                pos: bsp_data.verts[polyvert.vertex_id.0 as usize] + offset,
+                                normal: bsp_data.norms[polyvert.normal_id.0 as usize],
+                                uv: polyvert.uv,
+                            })
+                            .collect(),
+                        texture: Some(tri.texture),
+                    });
+                }
+            }
+        }
+
+        let count = count.min((MAX_DEBRIS_OBJECTS - self.num_debris_objects().min(MAX_DEBRIS_OBJECTS)) as usize);
+        if fragments.is_empty() || count == 0 {
+            return vec![];
+        }
+
+        // spread the seeds out as far from each other as possible, and then let them settle into the middle of their cells
+        let points = fragments
+            .iter()
+            .flat_map(|frag| frag.corners.iter().map(|corner| corner.pos))
+            .collect::<Vec<_>>();
+        let centroid = Vec3d::average(points.iter().copied());
+        let mut nearest_seed_dist = points.iter().map(|&point| (point - centroid).magnitude()).collect::<Vec<_>>();
+        let mut seeds = vec![];
+        while seeds.len() < count {
+            let (idx, &dist) = nearest_seed_dist.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap();
+            if dist <= 0.0 {
+                break;
+            }
+            seeds.push(points[idx]);
+            for (point, nearest) in points.iter().zip(&mut nearest_seed_dist) {
+                *nearest = nearest.min((*point - points[idx]).magnitude());
+            }
+        }
+        let nearest_seed = |seeds: &[Vec3d], point: Vec3d| {
+            (0..seeds.len())
+                .min_by(|&a, &b| (point - seeds[a]).magnitude().partial_cmp(&(point - seeds[b]).magnitude()).unwrap())
+                .unwrap()
+        };
+        for _ in 0..LLOYD_ITERATIONS {
+            let mut sums = vec![(Vec3d::ZERO, 0); seeds.len()];
+            for &point in &points {
+                let sum = &mut sums[nearest_seed(&seeds, point)];
+                sum.0 += point;
+                sum.1 += 1;
+            }
+            for (seed, (sum, n)) in seeds.iter_mut().zip(sums) {
+                if n > 0 {
+                    *seed = sum / n as f32;
+                }
+            }
+        }
+
+        let bbox = BoundingBox::from_vectors(points.iter().copied());
+        let uv_scale = 1.0 / bbox.size_on_axis(bbox.greatest_dimension()).max(f32::EPSILON);
+        let planar_corner = |pos: Vec3d, normal: Vec3d| {
+            let (u, v) = plane_basis(normal);
+            Corner {
+                pos,
+                normal,
+                uv: (pos.dot(u) * uv_scale, pos.dot(v) * uv_scale),
+            }
+        };
+
+        let mut new_ids = vec![];
+        for (i, &seed) in seeds.iter().enumerate() {
+            let mut piece = fragments.clone();
+            let mut capped = true;
+            for (j, &other) in seeds.iter().enumerate() {
+                if i == j || seed == other {
+                    continue;
+                }
+
+                let normal = (other - seed).normalize();
+                let dist = normal.dot((seed + other) / 2.0);
+                let mut cap_edges = vec![];
+                piece = piece
+                    .into_iter()
+                    .filter_map(|frag| {
+                        let (corners, cap_edge) = clip(&frag.corners, normal, dist);
+                        cap_edges.extend(cap_edge);
+                        (corners.len() >= 3).then(|| Fragment { corners, ..frag })
+                    })
+                    .collect();
+
+                if capped && !cap_edges.is_empty() {
+                    match cap(&cap_edges, normal) {
+                        Some(tris) => piece.extend(tris.into_iter().map(|tri| Fragment {
+                            corners: tri.iter().map(|&pos| planar_corner(pos, normal)).collect(),
+                            texture: None,
+                        })),
+                        None => capped = false,
+                    }
+                }
+            }
+
+            let mut texture_counts: HashMap<TextureId, usize> = HashMap::new();
+            for texture in piece.iter().filter_map(|frag| frag.texture) {
+                *texture_counts.entry(texture).or_default() += 1;
+            }
+            let cap_texture = match texture_counts.into_iter().max_by_key(|&(texture, n)| (n, Reverse(texture))) {
+                Some((texture, _)) => texture,
+                None => continue, // nothing of the model in this cell
+            };
+
+            if !capped {
+                let points = piece
+                    .iter()
+                    .flat_map(|frag| frag.corners.iter().map(|corner| corner.pos))
+                    .collect::<Vec<_>>();
+                piece = match convex_hull(&points) {
+                    Some(hull) => hull
+                        .into_iter()
+                        .map(|tri| {
+                            let [a, b, c] = tri.map(|k| points[k]);
+                            let normal = (b - a).cross(c - a).normalize();
+                            Fragment {
+                                corners: vec![planar_corner(a, normal), planar_corner(b, normal), planar_corner(c, normal)],
+                                texture: None,
+                            }
+                        })
+                        .collect(),
+                    None => continue,
+                };
+            }
+
+            let piece_bbox = BoundingBox::from_vectors(piece.iter().flat_map(|frag| frag.corners.iter().map(|corner| corner.pos)));
+            let center = (piece_bbox.min + piece_bbox.max) / 2.0;
+
+            let mut vert_map: HashMap<Vec3d, VertexId> = HashMap::new();
+            let mut norm_map: HashMap<Vec3d, NormalId> = HashMap::new();
+            let mut verts = vec![];
+            let mut norms = vec![];
+            let polygons = piece
+                .iter()
+                .map(|frag| Polygon {
+                    normal: Vec3d::ZERO,
+                    texture: frag.texture.unwrap_or(cap_texture),
+                    verts: frag
+                        .corners
+                        .iter()
+                        .map(|corner| PolyVertex {
+                            vertex_id: *vert_map.entry(corner.pos - center).or_insert_with(|| {
+                                verts.push(corner.pos - center);
+                                VertexId(verts.len() as u32 - 1)
+                            }),
+                            normal_id: *norm_map.entry(corner.normal.normalize()).or_insert_with(|| {
+                                norms.push(corner.normal.normalize());
+                                NormalId(norms.len() as u32 - 1)
+                            }),
+                            uv: corner.uv,
+                        })
+                        .collect(),
+                })
+                .collect::<Vec<_>>();
+
+            let new_id = ObjectId(self.sub_objects.len() as u32);
+            // the first free name, since deleted or oddly numbered debris can leave the count pointing at a taken one
+            let name = (1..)
+                .map(|i| format!("debris{:02}", i))
+                .find(|name| self.get_obj_id_by_name(name).is_none())
+                .unwrap();
+            let mut new_subobj = SubObject {
+                obj_id: new_id,
+                radius: Default::default(),
+                parent: None,
+                offset: center,
+                geo_center: Vec3d::ZERO,
+                bbox: Default::default(),
+                name,
+                properties: Default::default(),
+                movement_type: Default::default(),
+                movement_axis: Default::default(),
+                bsp_data: BspData {
+                    collision_tree: BspData::recalculate(&verts, polygons.into_iter()),
+                    verts,
+                    norms,
+                    extras: None,
+                },
+                children: Default::default(),
+                is_debris_model: true,
+            };
+            new_subobj.recalc_bbox();
+            new_subobj.recalc_radius();
+
+            self.sub_objects.push(new_subobj);
+            new_ids.push(new_id);
+        }
+
+        self.header.num_subobjects = self.sub_objects.len() as u32;
+        new_ids
+    }
 }
 
 pub fn post_parse_fill_untextured_slot(sub_objects: &mut Vec<SubObject>, textures: &mut Vec<String>) -> Option<TextureId> {
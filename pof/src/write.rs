@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     f32::consts::PI,
     io::{self, Write},
     mem::size_of,
@@ -16,8 +17,8 @@ use json::Index;
 extern crate nalgebra_glm as glm;
 
 use crate::{
-    BoundingBox, BspData, BspNode, Dock, EyePoint, GlowPointBank, Insignia, Model, ObjVec, ObjectId, Path, ShieldData, ShieldNode, SpecialPoint,
-    SubObject, ThrusterBank, Turret, Vec3d, Version, WeaponHardpoint,
+    BoundingBox, BspData, BspNode, CoordSystem, Dock, EyePoint, GlowPointBank, ImportOptions, Insignia, Mat3d, Model, ObjVec, ObjectId, Path,
+    ShieldData, ShieldNode, SpecialPoint, SubObject, SubsysMovementAxis, SubsysMovementType, ThrusterBank, Turret, Vec3d, Version, WeaponHardpoint,
 };
 
 pub(crate) trait Serialize {
@@ -115,6 +116,21 @@ impl Serialize for crate::NormalVec3 {
     }
 }
 
+// a uri to the file relative to the folder, unless there's no way to get from one to the other (different drives etc)
+fn relative_uri(path: &std::path::Path, dir: &std::path::Path) -> Option<String> {
+    let (path, dir) = (path.canonicalize().ok()?, dir.canonicalize().ok()?);
+    let common = path.components().zip(dir.components()).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let up = dir.components().skip(common).map(|_| "..".to_string());
+    let down = path
+        .components()
+        .skip(common)
+        .map(|component| urlencoding::encode(&component.as_os_str().to_string_lossy()).into_owned());
+    Some(up.chain(down).collect::<Vec<_>>().join("/"))
+}
+
 fn align_buf(buf: &mut Vec<u8>) -> io::Result<()> {
     let padding_length = buf.len().wrapping_neg() % 4;
     buf.write_all(&[0; 4][..padding_length])
@@ -481,6 +497,10 @@ trait NodeBuilder {
     fn scale(&mut self, val: [f32; 3]);
     fn matrix_transform(&mut self, mat: Mat4x4);
     fn build(self, ctx: &mut Self::Ctx) -> Self::Node;
+    // attaches a value to the node, as a "#{prefix}{key}:{value}" helper node, unless it can be stored in the node itself
+    fn push_metadata(&mut self, ctx: &mut Self::Ctx, prefix: &str, key: &str, value: impl ToString + Into<serde_json::Value>);
+    // same, for properties, which get a helper node per line
+    fn push_properties(&mut self, ctx: &mut Self::Ctx, prefix: &str, properties: &str);
 }
 trait Node {
     type Ctx;
@@ -511,6 +531,13 @@ impl NodeBuilder for DaeNode {
     fn build(self, _: &mut ()) -> DaeNode {
         self
     }
+    fn push_metadata(&mut self, _: &mut (), prefix: &str, key: &str, value: impl ToString + Into<serde_json::Value>) {
+        self.children
+            .push(DaeNode::new(format!("#{}{}", prefix, key), Some(format!("#{}{}:{}", prefix, key, value.to_string()))));
+    }
+    fn push_properties(&mut self, ctx: &mut (), prefix: &str, properties: &str) {
+        self.children.push(make_properties_node(ctx, properties, prefix.to_string()));
+    }
 }
 impl Node for DaeNode {
     type Ctx = ();
@@ -520,16 +547,33 @@ impl Node for DaeNode {
     }
 }
 
+// FSO's polygons are wound the other way around to those of DAE, glTF and OBJ files, unless the file is flipped as well
+fn wind_triangle<T>(coords: CoordSystem, [vert1, vert2, vert3]: [T; 3]) -> [T; 3] {
+    if coords.flip_winding() {
+        [vert1, vert2, vert3]
+    } else {
+        [vert1, vert3, vert2]
+    }
+}
+
+fn wind_polygon<T: Clone>(coords: CoordSystem, verts: &[T]) -> Vec<T> {
+    if coords.flip_winding() {
+        verts.to_vec()
+    } else {
+        verts.iter().rev().cloned().collect()
+    }
+}
+
 // turns a direction vector into an axis and angle (in radians)
 // mostly for the purposes of storing a normal into a node's transform
-fn vec_to_rotation(vec: &Vec3d, up: UpAxis) -> (Vec3d, f32) {
+fn vec_to_rotation(vec: &Vec3d, coords: CoordSystem) -> (Vec3d, f32) {
     let v1 = glm::Vec3::from(*vec).normalize();
     let v2 = glm::Vec3::z_axis();
     let mut cross = v1.cross(&v2);
     if cross.magnitude() < 0.001 {
         cross = *glm::Vec3::y_axis() // forward for DAE
     }
-    (Vec3d::from(cross.normalize()).to_coord(up), v1.dot(&v2).acos())
+    (Vec3d::from(cross.normalize()).to_coord_dir(coords), v1.dot(&v2).acos())
 }
 
 // turns properties into a series of dae nodes
@@ -545,7 +589,7 @@ fn make_properties_node<N: Node>(ctx: &mut N::Ctx, properties: &str, id: String)
     node.build(ctx)
 }
 
-fn make_thrusters_node<N: Node>(ctx: &mut N::Ctx, thruster_banks: &[ThrusterBank], up: UpAxis) -> N {
+fn make_thrusters_node<N: Node>(ctx: &mut N::Ctx, thruster_banks: &[ThrusterBank], coords: CoordSystem) -> N {
     let mut node = N::from_id("#thrusters".into());
 
     for (i, bank) in thruster_banks.iter().enumerate() {
@@ -553,19 +597,17 @@ fn make_thrusters_node<N: Node>(ctx: &mut N::Ctx, thruster_banks: &[ThrusterBank
 
         for (j, point) in bank.glows.iter().enumerate() {
             let mut point_node = N::from_id(format!("#tb{}-point{}", i, j));
-            let radius = point.radius;
-            let pos = point.position.to_coord(up);
+            let radius = point.radius / coords.scale();
+            let pos = point.position.to_coord(coords);
             point_node.translate(pos.into());
-            point_node.rotate(vec_to_rotation(&point.normal, up));
+            point_node.rotate(vec_to_rotation(&point.normal, coords));
             point_node.scale([radius, radius, radius]);
 
             bank_node.children().push(point_node.build(ctx));
         }
 
         if !bank.properties.is_empty() {
-            bank_node
-                .children()
-                .push(make_properties_node(ctx, &bank.properties, format!("tb{}-", i)));
+            bank_node.push_properties(ctx, &format!("tb{}-", i), &bank.properties);
         }
 
         node.children().push(bank_node.build(ctx));
@@ -574,7 +616,7 @@ fn make_thrusters_node<N: Node>(ctx: &mut N::Ctx, thruster_banks: &[ThrusterBank
     node.build(ctx)
 }
 
-fn make_paths_node<N: Node>(ctx: &mut N::Ctx, paths: &[Path], up: UpAxis) -> N {
+fn make_paths_node<N: Node>(ctx: &mut N::Ctx, paths: &[Path], subobjs: &ObjVec<SubObject>, coords: CoordSystem) -> N {
     let mut node = N::from_id("#paths".into());
 
     for (i, path) in paths.iter().enumerate() {
@@ -582,21 +624,22 @@ fn make_paths_node<N: Node>(ctx: &mut N::Ctx, paths: &[Path], up: UpAxis) -> N {
 
         for (j, point) in path.points.iter().enumerate() {
             let mut point_node = N::from_name(format!("#path{}-{}", i, j), format!("#p{}-point{}", i, j));
-            let radius = point.radius;
-            let pos = point.position.to_coord(up);
+            let radius = point.radius / coords.scale();
+            let pos = point.position.to_coord(coords);
             point_node.translate(pos.into());
             point_node.scale([radius, radius, radius]);
 
+            // turrets are referred to by name, since the ids may not survive editing
+            if !point.turrets.is_empty() {
+                let names = point.turrets.iter().map(|&id| subobjs[id].name.as_str()).collect::<Vec<_>>();
+                point_node.push_metadata(ctx, &format!("p{}-point{}-", i, j), "turrets", names.join(","));
+            }
+
             path_node.children().push(point_node.build(ctx));
         }
 
-        path_node
-            .children()
-            .push(N::from_name(format!("#p{}-name", i), format!("#p{}-name:{}", i, path.name)).build(ctx));
-
-        path_node
-            .children()
-            .push(N::from_name(format!("#p{}-parent", i), format!("#p{}-parent:{}", i, path.parent)).build(ctx));
+        path_node.push_metadata(ctx, &format!("p{}-", i), "name", path.name.clone());
+        path_node.push_metadata(ctx, &format!("p{}-", i), "parent", path.parent.clone());
 
         node.children().push(path_node.build(ctx));
     }
@@ -604,7 +647,7 @@ fn make_paths_node<N: Node>(ctx: &mut N::Ctx, paths: &[Path], up: UpAxis) -> N {
     node.build(ctx)
 }
 
-fn make_weapons_node<N: Node>(ctx: &mut N::Ctx, weapons: &[Vec<WeaponHardpoint>], kind: &str, up: UpAxis) -> N {
+fn make_weapons_node<N: Node>(ctx: &mut N::Ctx, weapons: &[Vec<WeaponHardpoint>], kind: &str, coords: CoordSystem) -> N {
     let mut node = N::from_id(format!("#{} weapons", kind));
 
     for (i, bank) in weapons.iter().enumerate() {
@@ -612,18 +655,12 @@ fn make_weapons_node<N: Node>(ctx: &mut N::Ctx, weapons: &[Vec<WeaponHardpoint>]
 
         for (j, point) in bank.iter().enumerate() {
             let mut point_node = N::from_id(format!("#w{}b{}-point{}", &kind[0..1], i, j));
-            let pos = point.position.to_coord(up);
+            let pos = point.position.to_coord(coords);
             point_node.translate(pos.into());
-            point_node.rotate(vec_to_rotation(&point.normal.0, up));
+            point_node.rotate(vec_to_rotation(&point.normal.0, coords));
 
             if point.offset != 0.0 {
-                point_node.children().push(
-                    N::from_name(
-                        format!("#w{}b{}-point{}-offset", &kind[0..1], i, j),
-                        format!("#w{}b{}-point{}-offset:{}", &kind[0..1], i, j, point.offset),
-                    )
-                    .build(ctx),
-                );
+                point_node.push_metadata(ctx, &format!("w{}b{}-point{}-", &kind[0..1], i, j), "offset", point.offset / coords.scale());
             }
 
             bank_node.children().push(point_node.build(ctx));
@@ -635,27 +672,25 @@ fn make_weapons_node<N: Node>(ctx: &mut N::Ctx, weapons: &[Vec<WeaponHardpoint>]
     node.build(ctx)
 }
 
-fn make_docking_bays_node<N: Node>(ctx: &mut N::Ctx, docks: &[Dock], up: UpAxis) -> N {
+fn make_docking_bays_node<N: Node>(ctx: &mut N::Ctx, docks: &[Dock], coords: CoordSystem) -> N {
     let mut node = N::from_id(format!("#docking bays"));
 
     for (i, dock) in docks.iter().enumerate() {
         let mut bay_node = N::from_id(format!("#bay{}", i));
 
-        let fvec: Vec3 = dock.fvec.0.to_coord(up).into();
-        let uvec = dock.uvec.0.to_coord(up).into();
+        let fvec: Vec3 = dock.fvec.0.to_coord_dir(coords).into();
+        let uvec = dock.uvec.0.to_coord_dir(coords).into();
         let mat = nalgebra::Matrix::from_columns(&[fvec.cross(&uvec), fvec, uvec]);
         let mut mat: Mat4x4 = glm::mat3_to_mat4(&mat);
-        mat.append_translation_mut(&dock.position.to_coord(up).into());
+        mat.append_translation_mut(&dock.position.to_coord(coords).into());
         bay_node.matrix_transform(mat);
 
-        if dock.path.is_some() {
-            bay_node
-                .children()
-                .push(N::from_name(format!("#d{}-path", i), format!("#d{}-path:{}", i, dock.path.unwrap().0 as u32)).build(ctx));
+        if let Some(path) = dock.path {
+            bay_node.push_metadata(ctx, &format!("d{}-", i), "path", path.0);
         }
 
         if !dock.properties.is_empty() {
-            bay_node.children().push(make_properties_node(ctx, &dock.properties, format!("d{}-", i)));
+            bay_node.push_properties(ctx, &format!("d{}-", i), &dock.properties);
         }
 
         node.children().push(bay_node.build(ctx));
@@ -664,7 +699,7 @@ fn make_docking_bays_node<N: Node>(ctx: &mut N::Ctx, docks: &[Dock], up: UpAxis)
     node.build(ctx)
 }
 
-fn make_glows_node<N: Node>(ctx: &mut N::Ctx, glows: &[GlowPointBank], up: UpAxis) -> N {
+fn make_glows_node<N: Node>(ctx: &mut N::Ctx, glows: &[GlowPointBank], coords: CoordSystem) -> N {
     let mut node = N::from_id("#glows".into());
 
     for (i, glow_bank) in glows.iter().enumerate() {
@@ -679,30 +714,27 @@ fn make_glows_node<N: Node>(ctx: &mut N::Ctx, glows: &[GlowPointBank], up: UpAxi
                     format!("#g{}-point{}", i, j)
                 },
             );
-            let radius = point.radius;
-            let pos = point.position.to_coord(up);
+            let radius = point.radius / coords.scale();
+            let pos = point.position.to_coord(coords);
             point_node.translate(pos.into());
             if !point.normal.is_null() {
-                point_node.rotate(vec_to_rotation(&point.normal, up));
+                point_node.rotate(vec_to_rotation(&point.normal, coords));
             }
             point_node.scale([radius, radius, radius]);
 
             bank_node.children().push(point_node.build(ctx));
         }
 
-        bank_node.children().extend([
-            N::from_name(format!("#g{}-type", i), format!("#g{}-type:{}", i, glow_bank.glow_type)).build(ctx),
-            N::from_name(format!("#g{}-lod", i), format!("#g{}-lod:{}", i, glow_bank.lod)).build(ctx),
-            N::from_name(format!("#g{}-parent", i), format!("#g{}-parent:{}", i, glow_bank.obj_parent.0)).build(ctx),
-            N::from_name(format!("#g{}-ontime", i), format!("#g{}-ontime:{}", i, glow_bank.on_time)).build(ctx),
-            N::from_name(format!("#g{}-offtime", i), format!("#g{}-offtime:{}", i, glow_bank.off_time)).build(ctx),
-            N::from_name(format!("#g{}-disptime", i), format!("#g{}-disptime:{}", i, glow_bank.disp_time)).build(ctx),
-        ]);
+        let prefix = format!("g{}-", i);
+        bank_node.push_metadata(ctx, &prefix, "type", glow_bank.glow_type);
+        bank_node.push_metadata(ctx, &prefix, "lod", glow_bank.lod);
+        bank_node.push_metadata(ctx, &prefix, "parent", glow_bank.obj_parent.0);
+        bank_node.push_metadata(ctx, &prefix, "ontime", glow_bank.on_time);
+        bank_node.push_metadata(ctx, &prefix, "offtime", glow_bank.off_time);
+        bank_node.push_metadata(ctx, &prefix, "disptime", glow_bank.disp_time);
 
         if !glow_bank.properties.is_empty() {
-            bank_node
-                .children()
-                .push(make_properties_node(ctx, &glow_bank.properties, format!("g{}-", i)));
+            bank_node.push_properties(ctx, &prefix, &glow_bank.properties);
         }
 
         node.children().push(bank_node.build(ctx));
@@ -711,21 +743,19 @@ fn make_glows_node<N: Node>(ctx: &mut N::Ctx, glows: &[GlowPointBank], up: UpAxi
     node.build(ctx)
 }
 
-fn make_specials_node<N: Node>(ctx: &mut N::Ctx, special_points: &[SpecialPoint], up: UpAxis) -> N {
+fn make_specials_node<N: Node>(ctx: &mut N::Ctx, special_points: &[SpecialPoint], coords: CoordSystem) -> N {
     let mut node = N::from_id(format!("#special points"));
 
     for (i, point) in special_points.iter().enumerate() {
         let mut point_node = N::from_name(format!("#s{}", i), format!("#s{}:{}", i, point.name));
 
-        let radius = point.radius;
-        let pos = point.position.to_coord(up);
+        let radius = point.radius / coords.scale();
+        let pos = point.position.to_coord(coords);
         point_node.translate(pos.into());
         point_node.scale([radius, radius, radius]);
 
         if !point.properties.is_empty() {
-            point_node
-                .children()
-                .push(make_properties_node(ctx, &point.properties, format!("s{}-", i)));
+            point_node.push_properties(ctx, &format!("s{}-", i), &point.properties);
         }
 
         node.children().push(point_node.build(ctx));
@@ -734,19 +764,17 @@ fn make_specials_node<N: Node>(ctx: &mut N::Ctx, special_points: &[SpecialPoint]
     node.build(ctx)
 }
 
-fn make_eyes_node<N: Node>(ctx: &mut N::Ctx, eye_points: &[EyePoint], up: UpAxis) -> N {
+fn make_eyes_node<N: Node>(ctx: &mut N::Ctx, eye_points: &[EyePoint], coords: CoordSystem) -> N {
     let mut node = N::from_id(format!("#eye points"));
 
     for (i, point) in eye_points.iter().enumerate() {
         let mut point_node = N::from_name(format!("#e{}", i), format!("#e-point{}", i));
 
-        let pos = point.offset.to_coord(up);
+        let pos = point.offset.to_coord(coords);
         point_node.translate(pos.into());
-        point_node.rotate(vec_to_rotation(&point.normal.0, up));
+        point_node.rotate(vec_to_rotation(&point.normal.0, coords));
 
-        point_node
-            .children()
-            .push(N::from_name(format!("#e{}-parent", i), format!("#e{}-parent:{}", i, point.attached_subobj.0)).build(ctx));
+        point_node.push_metadata(ctx, &format!("e{}-", i), "parent", point.attached_subobj.0);
 
         node.children().push(point_node.build(ctx));
     }
@@ -754,15 +782,51 @@ fn make_eyes_node<N: Node>(ctx: &mut N::Ctx, eye_points: &[EyePoint], up: UpAxis
     node.build(ctx)
 }
 
-fn make_visual_center_node<N: Node>(ctx: &mut N::Ctx, visual_center: &Vec3d, up: UpAxis) -> N {
+fn make_visual_center_node<N: Node>(ctx: &mut N::Ctx, visual_center: &Vec3d, coords: CoordSystem) -> N {
     let mut node = N::from_id(format!("#visual-center"));
 
-    node.translate(visual_center.to_coord(up).into());
+    node.translate(visual_center.to_coord(coords).into());
+
+    node.build(ctx)
+}
+
+// the header values that can't be rebuilt from the geometry, which replace the recalculated ones on import
+// these are kept in pof coordinates, as lists of numbers, since they aren't anything that could be placed in the scene
+fn make_header_node<N: Node>(ctx: &mut N::Ctx, model: &Model) -> N {
+    fn floats(vals: impl IntoIterator<Item = f32>) -> String {
+        vals.into_iter().map(|val| val.to_string()).collect::<Vec<_>>().join(" ")
+    }
+    let header = &model.header;
+    let mut node = N::from_id(format!("#header"));
+
+    let Vec3d { x, y, z } = header.center_of_mass;
+    let Mat3d { rvec, uvec, fvec } = header.moment_of_inertia;
+    node.push_metadata(ctx, "header-", "mass", header.mass);
+    node.push_metadata(ctx, "header-", "center-of-mass", floats([x, y, z]));
+    node.push_metadata(ctx, "header-", "moi", floats([rvec, uvec, fvec].into_iter().flat_map(|vec| [vec.x, vec.y, vec.z])));
+    node.push_metadata(ctx, "header-", "flags", header.obj_flags);
+
+    if !header.cross_sections.is_empty() {
+        let sections = floats(header.cross_sections.iter().flat_map(|&(depth, radius)| [depth, radius]));
+        node.push_metadata(ctx, "header-", "cross-sections", sections);
+    }
+
+    if !header.bsp_lights.is_empty() {
+        let lights = floats(header.bsp_lights.iter().flat_map(|light| {
+            let Vec3d { x, y, z } = light.location;
+            [x, y, z, light.kind as u32 as f32]
+        }));
+        node.push_metadata(ctx, "header-", "bsp-lights", lights);
+    }
+
+    if !model.comments.is_empty() {
+        node.push_metadata(ctx, "header-", "comments", escape_metadata(&model.comments));
+    }
 
     node.build(ctx)
 }
 
-fn make_insignia_node(insignia: &Insignia, geometries: &mut Vec<Geometry>, id: usize, up: UpAxis) -> DaeNode {
+fn make_insignia_node(insignia: &Insignia, geometries: &mut Vec<Geometry>, id: usize, coords: CoordSystem) -> DaeNode {
     let geo_id = format!("insig{}-geometry", id);
     let pos_id = format!("insig{}-geometry-position", id);
     let vert_id = format!("insig{}-geometry-vertex", id);
@@ -770,19 +834,16 @@ fn make_insignia_node(insignia: &Insignia, geometries: &mut Vec<Geometry>, id: u
 
     let mut positions = vec![];
     for vert in &insignia.vertices {
-        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(up)));
+        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(coords)));
     }
 
     let mut tricount = 0;
     let mut indices = vec![];
 
     for (polyvert1, polyvert2, polyvert3) in &insignia.faces {
-        indices.push(polyvert1.vertex_id.0 as _);
-
-        // intentional swizzle
-        indices.push(polyvert3.vertex_id.0 as _);
-
-        indices.push(polyvert2.vertex_id.0 as _);
+        for polyvert in wind_triangle(coords, [polyvert1, polyvert2, polyvert3]) {
+            indices.push(polyvert.vertex_id.0 as _);
+        }
 
         tricount += 1;
     }
@@ -808,12 +869,12 @@ fn make_insignia_node(insignia: &Insignia, geometries: &mut Vec<Geometry>, id: u
     let mut node = DaeNode::new(format!("insig{}", id), Some(format!("insignia {}", id)));
 
     node.instance_geometry.push(instance);
-    node.translate(insignia.offset.to_coord(up).into());
+    node.translate(insignia.offset.to_coord(coords).into());
 
     node
 }
 
-fn make_shield_node(shield: &ShieldData, geometries: &mut Vec<Geometry>, up: UpAxis) -> DaeNode {
+fn make_shield_node(shield: &ShieldData, geometries: &mut Vec<Geometry>, coords: CoordSystem) -> DaeNode {
     let geo_id = format!("shield-geometry");
     let pos_id = format!("shield-geometry-position");
     let vert_id = format!("shield-geometry-vertex");
@@ -823,7 +884,7 @@ fn make_shield_node(shield: &ShieldData, geometries: &mut Vec<Geometry>, up: UpA
 
     let mut positions = vec![];
     for vert in &shield.verts {
-        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(up)));
+        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(coords)));
     }
 
     let mut normals = vec![];
@@ -831,17 +892,13 @@ fn make_shield_node(shield: &ShieldData, geometries: &mut Vec<Geometry>, up: UpA
     let mut indices = vec![];
 
     for poly in &shield.polygons {
-        normals.extend_from_slice(&<[_; 3]>::from(poly.normal.to_coord(up)));
-
-        indices.push(poly.verts.0 .0 as _);
-        indices.push(tricount as _);
-
-        // intentional swizzle
-        indices.push(poly.verts.2 .0 as _);
-        indices.push(tricount as _);
+        normals.extend_from_slice(&<[_; 3]>::from(poly.normal.to_coord_dir(coords)));
 
-        indices.push(poly.verts.1 .0 as _);
-        indices.push(tricount as _);
+        let (vert1, vert2, vert3) = poly.verts;
+        for vert in wind_triangle(coords, [vert1, vert2, vert3]) {
+            indices.push(vert.0 as _);
+            indices.push(tricount as _);
+        }
 
         tricount += 1;
     }
@@ -878,7 +935,7 @@ fn make_shield_node(shield: &ShieldData, geometries: &mut Vec<Geometry>, up: UpA
 }
 
 fn make_subobj_node(
-    up: UpAxis, subobjs: &ObjVec<SubObject>, subobj: &SubObject, turrets: &[Turret], geometries: &mut Vec<Geometry>, materials: &[String],
+    coords: CoordSystem, subobjs: &ObjVec<SubObject>, subobj: &SubObject, turrets: &[Turret], geometries: &mut Vec<Geometry>, materials: &[String],
 ) -> DaeNode {
     let geo_id = format!("{}-geometry", subobj.name);
     let pos_id = format!("{}-geometry-position", subobj.name);
@@ -891,31 +948,71 @@ fn make_subobj_node(
 
     let mut positions = vec![];
     for vert in &subobj.bsp_data.verts {
-        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(up)));
+        positions.extend_from_slice(&<[_; 3]>::from(vert.to_coord(coords)));
     }
 
     let mut normals = vec![];
     for norm in &subobj.bsp_data.norms {
-        normals.extend_from_slice(&<[_; 3]>::from(norm.to_coord(up)));
+        normals.extend_from_slice(&<[_; 3]>::from(norm.to_coord_dir(coords)));
     }
 
     let mut uv_coords = vec![];
     let mut uv_len = 0;
     let mut prim_elems = vec![(vec![], vec![]); materials.len() + 1];
 
+    // any further uv sets and colors are indexed the same as the first uv set, but each needs its own offset
+    let extras = subobj.bsp_data.extras.as_ref();
+    let mut extra_uv_coords = vec![vec![]; extras.map_or(0, |extras| extras.uv_sets.len())];
+    let has_colors = matches!(extras, Some(extras) if !extras.colors.is_empty());
+    let mut colors = vec![];
+
     for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
         let (vert_count, indices) = &mut prim_elems[poly.texture.0 as usize + 1];
         vert_count.push(poly.verts.len() as u32);
-        for vert in poly.verts.iter().rev() {
+        for vert in wind_polygon(coords, &poly.verts) {
             indices.push(vert.vertex_id.0 as _);
             indices.push(vert.normal_id.0 as _);
-            indices.push(uv_len);
+            for _ in 0..1 + extra_uv_coords.len() + has_colors as usize {
+                indices.push(uv_len);
+            }
             uv_len += 1;
             uv_coords.push(vert.uv.0);
             uv_coords.push(1. - vert.uv.1);
+            if let Some(extras) = extras {
+                for (set, uv_coords) in extra_uv_coords.iter_mut().enumerate() {
+                    let (u, v) = extras.uv(set, &vert);
+                    uv_coords.push(u);
+                    uv_coords.push(1. - v);
+                }
+                if has_colors {
+                    colors.extend_from_slice(&extras.color(&vert));
+                }
+            }
         }
     }
 
+    let mut extra_sources = vec![];
+    let mut extra_inputs = vec![];
+    for (set, uv_coords) in extra_uv_coords.into_iter().enumerate() {
+        let id = format!("{}{}", uv_id, set + 1);
+        extra_sources.push(Source::new_local(
+            id.clone(),
+            Param::new_st(),
+            ArrayElement::Float(FloatArray { id: Some(format!("{}-array", id)), val: uv_coords.into() }),
+        ));
+        extra_inputs.push((Semantic::TexCoord, id, Some(set as u32 + 1)));
+    }
+    if has_colors {
+        let id = format!("{}-geometry-color", subobj.name);
+        let params = ["R", "G", "B", "A"].into_iter().map(|name| Param::new(name, "float")).collect();
+        extra_sources.push(Source::new_local(
+            id.clone(),
+            params,
+            ArrayElement::Float(FloatArray { id: Some(format!("{}-array", id)), val: colors.into() }),
+        ));
+        extra_inputs.push((Semantic::Color, id, Some(0)));
+    }
+
     let mut instance = Instance::<Geometry>::new(Url::Fragment(geo_id.clone()));
     let bind_materials = prim_elems[1..]
         .iter()
@@ -937,7 +1034,10 @@ fn make_subobj_node(
             ),
             Source::new_local(norm_id.clone(), Param::new_xyz(), ArrayElement::Float(FloatArray { id: Some(norm_array_id), val: normals.into() })),
             Source::new_local(uv_id.clone(), Param::new_st(), ArrayElement::Float(FloatArray { id: Some(uv_array_id), val: uv_coords.into() })),
-        ],
+        ]
+        .into_iter()
+        .chain(extra_sources)
+        .collect(),
         Vertices::new(vert_id.clone(), vec![Input::new(Semantic::Position, Url::Fragment(pos_id))]),
         prim_elems
             .into_iter()
@@ -947,11 +1047,19 @@ fn make_subobj_node(
                 // TODO maybe triangles sometimes?
                 Primitive::PolyList(PolyList::new(
                     material.cloned(),
-                    vec![
+                    [
                         InputS::new(Semantic::Vertex, Url::Fragment(vert_id.clone()), 0, None),
                         InputS::new(Semantic::Normal, Url::Fragment(norm_id.clone()), 1, None),
                         InputS::new(Semantic::TexCoord, Url::Fragment(uv_id.clone()), 2, None),
-                    ],
+                    ]
+                    .into_iter()
+                    .chain(
+                        extra_inputs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (semantic, id, set))| InputS::new(semantic.clone(), Url::Fragment(id.clone()), 3 + i as u32, *set)),
+                    )
+                    .collect(),
                     vcount.into_boxed_slice(),
                     indices.into_boxed_slice(),
                 ))
@@ -960,7 +1068,7 @@ fn make_subobj_node(
     ));
 
     let mut node = DaeNode::from_id(subobj.name.clone());
-    node.translate(subobj.offset.to_coord(up).into());
+    node.translate(subobj.offset.to_coord(coords).into());
 
     // kind of expensive to do per subobj?
     for (i, turret) in turrets.iter().enumerate() {
@@ -973,8 +1081,8 @@ fn make_subobj_node(
                 };
 
                 let mut gunpoint_node = DaeNode::from_id(name);
-                gunpoint_node.translate(point.to_coord(up).into());
-                gunpoint_node.rotate(vec_to_rotation(&turret.normal.0, up));
+                gunpoint_node.translate(point.to_coord(coords).into());
+                gunpoint_node.rotate(vec_to_rotation(&turret.normal.0, coords));
                 node.children().push(gunpoint_node);
             }
         }
@@ -1000,28 +1108,29 @@ fn make_subobj_node(
         subobj
             .children
             .iter()
-            .map(|&id| make_subobj_node(up, subobjs, &subobjs[id], turrets, geometries, materials)),
+            .map(|&id| make_subobj_node(coords, subobjs, &subobjs[id], turrets, geometries, materials)),
     );
 
     node
 }
 
 impl Model {
-    pub fn write_dae(&self, w: &mut impl Write) -> Result<(), dae_parser::Error> {
+    // the options say which of the file's axes are up and forward, and its scale, the up axis is also written to the file
+    pub fn write_dae(&self, w: &mut impl Write, options: &ImportOptions) -> Result<(), dae_parser::Error> {
         let mut geometries = vec![];
 
         let mut nodes = vec![];
         let materials: Vec<String> = self.textures.iter().map(|tex| format!("{}-material", tex)).collect();
 
-        let up = UpAxis::YUp;
+        let coords = options.coords(UpAxis::YUp);
 
         for subobj in &self.sub_objects {
             if subobj.parent.is_none() {
-                let mut top_level_node = make_subobj_node(up, &self.sub_objects, subobj, &self.turrets, &mut geometries, &materials);
+                let mut top_level_node = make_subobj_node(coords, &self.sub_objects, subobj, &self.turrets, &mut geometries, &materials);
 
                 for (i, insignia) in self.insignias.iter().enumerate() {
                     if self.get_detail_level(subobj.obj_id) == Some(insignia.detail_level) {
-                        top_level_node.children.push(make_insignia_node(insignia, &mut geometries, i, up))
+                        top_level_node.children.push(make_insignia_node(insignia, &mut geometries, i, coords))
                     }
                 }
 
@@ -1030,45 +1139,47 @@ impl Model {
         }
 
         if let Some(shield_data) = &self.shield_data {
-            nodes.push(make_shield_node(shield_data, &mut geometries, up));
+            nodes.push(make_shield_node(shield_data, &mut geometries, coords));
         }
 
         if !self.thruster_banks.is_empty() {
-            nodes.push(make_thrusters_node(&mut (), &self.thruster_banks, up));
+            nodes.push(make_thrusters_node(&mut (), &self.thruster_banks, coords));
         }
 
         if !self.paths.is_empty() {
-            nodes.push(make_paths_node(&mut (), &self.paths, up));
+            nodes.push(make_paths_node(&mut (), &self.paths, &self.sub_objects, coords));
         }
 
         if !self.primary_weps.is_empty() {
-            nodes.push(make_weapons_node(&mut (), &self.primary_weps, "primary", up));
+            nodes.push(make_weapons_node(&mut (), &self.primary_weps, "primary", coords));
         }
 
         if !self.secondary_weps.is_empty() {
-            nodes.push(make_weapons_node(&mut (), &self.secondary_weps, "secondary", up));
+            nodes.push(make_weapons_node(&mut (), &self.secondary_weps, "secondary", coords));
         }
 
         if !self.docking_bays.is_empty() {
-            nodes.push(make_docking_bays_node(&mut (), &self.docking_bays, up));
+            nodes.push(make_docking_bays_node(&mut (), &self.docking_bays, coords));
         }
 
         if !self.glow_banks.is_empty() {
-            nodes.push(make_glows_node(&mut (), &self.glow_banks, up));
+            nodes.push(make_glows_node(&mut (), &self.glow_banks, coords));
         }
 
         if !self.special_points.is_empty() {
-            nodes.push(make_specials_node(&mut (), &self.special_points, up));
+            nodes.push(make_specials_node(&mut (), &self.special_points, coords));
         }
 
         if !self.eye_points.is_empty() {
-            nodes.push(make_eyes_node(&mut (), &self.eye_points, up));
+            nodes.push(make_eyes_node(&mut (), &self.eye_points, coords));
         }
 
         if !self.visual_center.is_null() {
-            nodes.push(make_visual_center_node(&mut (), &self.visual_center, up));
+            nodes.push(make_visual_center_node(&mut (), &self.visual_center, coords));
         }
 
+        nodes.push(make_header_node(&mut (), self));
+
         let mut doc = Document::create_now();
         doc.push_library(
             self.textures
@@ -1092,7 +1203,7 @@ impl Model {
 
         doc.scene = Some(Scene::new(Instance::new(Url::Fragment("Scene".to_string()))));
 
-        doc.asset.up_axis = up;
+        doc.asset.up_axis = coords.up().into();
 
         doc.write_to(w)
     }
@@ -1101,11 +1212,33 @@ impl Model {
 #[derive(Default)]
 struct GltfBuilder {
     root: json::Root,
+    nodes: GltfNodes,
     buffer: Vec<u8>,
+    options: ImportOptions,
+    // the node each subobject ended up as, for animating them
+    subobj_nodes: HashMap<ObjectId, NodeIndex>,
+}
+
+// the nodes of the glTF being built
+#[derive(Default)]
+struct GltfNodes {
+    nodes: Vec<json::Node>,
+    // whether metadata (properties etc) goes in the nodes' extras, rather than in helper nodes like dae
+    metadata_in_extras: bool,
+}
+
+// adds a key to a node's extras, which are kept as a json object
+fn set_extra(extras: &mut json::Extras, key: &str, value: serde_json::Value) {
+    let mut map = extras
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(extras.get()).ok())
+        .unwrap_or_default();
+    map.insert(key.to_string(), value);
+    *extras = serde_json::value::to_raw_value(&map).ok();
 }
 
 impl NodeBuilder for json::Node {
-    type Ctx = Vec<Self>;
+    type Ctx = GltfNodes;
     type Node = Index<Self>;
     fn children(&mut self) -> &mut Vec<Index<Self>> {
         self.children.get_or_insert_with(Default::default)
@@ -1123,13 +1256,29 @@ impl NodeBuilder for json::Node {
     fn matrix_transform(&mut self, mat: Mat4x4) {
         self.matrix = Some(*Matrix::from(mat).0)
     }
-    fn build(self, ctx: &mut Vec<Self>) -> Index<Self> {
-        GltfBuilder::push(ctx, self)
+    fn build(self, ctx: &mut GltfNodes) -> Index<Self> {
+        GltfBuilder::push(&mut ctx.nodes, self)
+    }
+    fn push_metadata(&mut self, ctx: &mut GltfNodes, prefix: &str, key: &str, value: impl ToString + Into<serde_json::Value>) {
+        if ctx.metadata_in_extras {
+            set_extra(&mut self.extras, key, value.into());
+        } else {
+            let node = NodeIndex::from_id(format!("#{}{}:{}", prefix, key, value.to_string())).build(ctx);
+            self.children().push(node);
+        }
+    }
+    fn push_properties(&mut self, ctx: &mut GltfNodes, prefix: &str, properties: &str) {
+        if ctx.metadata_in_extras {
+            set_extra(&mut self.extras, "properties", properties.into());
+        } else {
+            let node = make_properties_node(ctx, properties, prefix.to_string());
+            self.children().push(node);
+        }
     }
 }
 type NodeIndex = Index<json::Node>;
 impl Node for NodeIndex {
-    type Ctx = Vec<json::Node>;
+    type Ctx = GltfNodes;
     type Builder = json::Node;
     fn from_name(_: String, name: String) -> json::Node {
         Self::from_id(name)
@@ -1159,7 +1308,7 @@ impl GltfBuilder {
         Index::new(n.try_into().unwrap())
     }
 
-    fn push_buffer_view(&mut self, offset: usize, size: usize, packed: bool, count: usize, target: Target) -> Index<json::buffer::View> {
+    fn push_buffer_view(&mut self, offset: usize, size: usize, packed: bool, count: usize, target: Option<Target>) -> Index<json::buffer::View> {
         Self::push(
             &mut self.root.buffer_views,
             json::buffer::View {
@@ -1170,7 +1319,7 @@ impl GltfBuilder {
                 extensions: None,
                 extras: Default::default(),
                 name: None,
-                target: Some(Valid(target)),
+                target: target.map(Valid),
             },
         )
     }
@@ -1211,25 +1360,24 @@ impl GltfBuilder {
         )
     }
 
-    fn make_insignia_node(&mut self, insignia: &Insignia, id: usize, up: UpAxis) -> NodeIndex {
+    fn make_insignia_node(&mut self, insignia: &Insignia, id: usize, coords: CoordSystem) -> NodeIndex {
         let start = self.buffer.len();
-        let view = self.push_buffer_view(start, size_of::<Vec3d>(), true, insignia.vertices.len(), Target::ArrayBuffer);
+        let view = self.push_buffer_view(start, size_of::<Vec3d>(), true, insignia.vertices.len(), Some(Target::ArrayBuffer));
         let bbox = BoundingBox::from_vectors(insignia.vertices.iter().map(|vert| {
-            let vert = vert.to_coord(up);
+            let vert = vert.to_coord(coords);
             vert.write_to(&mut self.buffer).unwrap();
             vert
         }));
 
         let indices = self.buffer.len();
         let count = 3 * insignia.faces.len();
-        let indices = self.push_buffer_view(indices, size_of::<u16>(), true, count, Target::ElementArrayBuffer);
+        let indices = self.push_buffer_view(indices, size_of::<u16>(), true, count, Some(Target::ElementArrayBuffer));
         let indices = self.push_accessor(indices, 0, count, ComponentType::U16, json::accessor::Type::Scalar, None);
 
         for (polyvert1, polyvert2, polyvert3) in &insignia.faces {
-            // intentional swizzle
-            (polyvert1.vertex_id.0 as u16).write_to(&mut self.buffer).unwrap();
-            (polyvert3.vertex_id.0 as u16).write_to(&mut self.buffer).unwrap();
-            (polyvert2.vertex_id.0 as u16).write_to(&mut self.buffer).unwrap();
+            for polyvert in wind_triangle(coords, [polyvert1, polyvert2, polyvert3]) {
+                (polyvert.vertex_id.0 as u16).write_to(&mut self.buffer).unwrap();
+            }
         }
 
         let attributes = [(
@@ -1249,28 +1397,27 @@ impl GltfBuilder {
             targets: None,
         }]));
 
-        node.translate(insignia.offset.to_coord(up).into());
-        node.build(&mut self.root.nodes)
+        node.translate(insignia.offset.to_coord(coords).into());
+        node.build(&mut self.nodes)
     }
 
-    fn make_shield_node(&mut self, shield: &ShieldData, up: UpAxis) -> NodeIndex {
+    fn make_shield_node(&mut self, shield: &ShieldData, coords: CoordSystem) -> NodeIndex {
         let start = self.buffer.len();
         let count = 3 * shield.polygons.len();
-        let view = self.push_buffer_view(start, size_of::<(Vec3d, Vec3d)>(), false, count, Target::ArrayBuffer);
+        let view = self.push_buffer_view(start, size_of::<(Vec3d, Vec3d)>(), false, count, Some(Target::ArrayBuffer));
 
         let mut bbox_pos = BoundingBox::EMPTY;
         let mut bbox_norm = BoundingBox::EMPTY;
         for poly in &shield.polygons {
             let (v1, v2, v3) = poly.verts;
-            let v1 = shield.verts[v1.0 as usize].to_coord(up);
-            let v2 = shield.verts[v2.0 as usize].to_coord(up);
-            let v3 = shield.verts[v3.0 as usize].to_coord(up);
-            let normal = poly.normal.to_coord(up);
+            let v1 = shield.verts[v1.0 as usize].to_coord(coords);
+            let v2 = shield.verts[v2.0 as usize].to_coord(coords);
+            let v3 = shield.verts[v3.0 as usize].to_coord(coords);
+            let normal = poly.normal.to_coord_dir(coords);
 
-            // intentional swizzle
-            (v1, normal).write_to(&mut self.buffer).unwrap();
-            (v3, normal).write_to(&mut self.buffer).unwrap();
-            (v2, normal).write_to(&mut self.buffer).unwrap();
+            for vert in wind_triangle(coords, [v1, v2, v3]) {
+                (vert, normal).write_to(&mut self.buffer).unwrap();
+            }
 
             bbox_pos.expand_vec(v1);
             bbox_pos.expand_vec(v2);
@@ -1300,21 +1447,29 @@ impl GltfBuilder {
             targets: None,
         }]));
 
-        node.build(&mut self.root.nodes)
+        node.build(&mut self.nodes)
     }
 
     fn make_subobj_node(&mut self, subobjs: &ObjVec<SubObject>, subobj: &SubObject, turrets: &[Turret], materials: usize) -> json::Node {
+        let coords = self.options.coords(UpAxis::YUp);
         let mut prim_elems = vec![vec![]; materials];
         for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
             if let [vert1, rest @ ..] = &*poly.verts {
                 let tris = &mut prim_elems[poly.texture.0 as usize];
                 for verts in rest.windows(2) {
-                    // intentional swizzle
-                    tris.push([vert1, &verts[1], &verts[0]]);
+                    tris.push(wind_triangle(coords, [vert1, &verts[0], &verts[1]]));
                 }
             }
         }
-        let up = UpAxis::YUp;
+
+        // any further uv sets and colors are interleaved after the first uv
+        let extras = subobj.bsp_data.extras.as_ref();
+        let num_uv_sets = extras.map_or(0, |extras| extras.uv_sets.len());
+        let has_colors = matches!(extras, Some(extras) if !extras.colors.is_empty());
+        let uvs_offset = size_of::<(Vec3d, Vec3d, [f32; 2])>();
+        let colors_offset = uvs_offset + num_uv_sets * size_of::<[f32; 2]>();
+        let stride = colors_offset + if has_colors { size_of::<[f32; 4]>() } else { 0 };
+
         let primitives = prim_elems
             .into_iter()
             .enumerate()
@@ -1322,15 +1477,25 @@ impl GltfBuilder {
             .map(|(material, prim_elem)| {
                 let start = self.buffer.len();
                 let count = 3 * prim_elem.len();
-                let view = self.push_buffer_view(start, size_of::<(Vec3d, Vec3d, [f32; 2])>(), false, count, Target::ArrayBuffer);
+                let view = self.push_buffer_view(start, stride, false, count, Some(Target::ArrayBuffer));
                 let mut bbox_pos = BoundingBox::EMPTY;
                 for vert in prim_elem.into_iter().flatten() {
-                    let position = subobj.bsp_data.verts[vert.vertex_id.0 as usize].to_coord(up);
-                    let normal = subobj.bsp_data.norms[vert.normal_id.0 as usize].to_coord(up);
+                    let position = subobj.bsp_data.verts[vert.vertex_id.0 as usize].to_coord(coords);
+                    let normal = subobj.bsp_data.norms[vert.normal_id.0 as usize].to_coord_dir(coords);
                     bbox_pos.expand_vec(position);
                     (position, normal, vert.uv).write_to(&mut self.buffer).unwrap();
+                    if let Some(extras) = extras {
+                        for set in 0..num_uv_sets {
+                            extras.uv(set, vert).write_to(&mut self.buffer).unwrap();
+                        }
+                        if has_colors {
+                            for channel in extras.color(vert) {
+                                self.buffer.write_f32::<LE>(channel).unwrap();
+                            }
+                        }
+                    }
                 }
-                let attributes = [
+                let mut attributes = vec![
                     (
                         Valid(json::mesh::Semantic::Positions),
                         self.push_accessor(view, 0, count, ComponentType::F32, json::accessor::Type::Vec3, Some(bbox_pos)),
@@ -1344,6 +1509,19 @@ impl GltfBuilder {
                         self.push_accessor(view, 2 * size_of::<Vec3d>(), count, ComponentType::F32, json::accessor::Type::Vec2, None),
                     ),
                 ];
+                for set in 0..num_uv_sets {
+                    let offset = uvs_offset + set * size_of::<[f32; 2]>();
+                    attributes.push((
+                        Valid(json::mesh::Semantic::TexCoords(set as u32 + 1)),
+                        self.push_accessor(view, offset, count, ComponentType::F32, json::accessor::Type::Vec2, None),
+                    ));
+                }
+                if has_colors {
+                    attributes.push((
+                        Valid(json::mesh::Semantic::Colors(0)),
+                        self.push_accessor(view, colors_offset, count, ComponentType::F32, json::accessor::Type::Vec4, None),
+                    ));
+                }
                 json::mesh::Primitive {
                     attributes: attributes.into_iter().collect(),
                     extensions: Default::default(),
@@ -1359,7 +1537,7 @@ impl GltfBuilder {
         let geo_id = self.push_mesh(primitives);
 
         let mut node = NodeIndex::from_id(subobj.name.clone());
-        node.translate(subobj.offset.to_coord(up).into());
+        node.translate(subobj.offset.to_coord(coords).into());
 
         // kind of expensive to do per subobj?
         for (i, turret) in turrets.iter().enumerate() {
@@ -1372,71 +1550,311 @@ impl GltfBuilder {
                     };
 
                     let mut gunpoint_node = NodeIndex::from_id(name);
-                    gunpoint_node.translate(point.to_coord(up).into());
-                    gunpoint_node.rotate(vec_to_rotation(&turret.normal.0, up));
-                    node.children().push(gunpoint_node.build(&mut self.root.nodes));
+                    gunpoint_node.translate(point.to_coord(coords).into());
+                    gunpoint_node.rotate(vec_to_rotation(&turret.normal.0, coords));
+                    node.children().push(gunpoint_node.build(&mut self.nodes));
                 }
             }
         }
 
+        let prefix = format!("{}-", subobj.name);
         if !subobj.properties.is_empty() {
-            node.children()
-                .push(make_properties_node(&mut self.root.nodes, &subobj.properties, format!("{}-", subobj.name)));
+            node.push_properties(&mut self.nodes, &prefix, &subobj.properties);
         }
 
         if subobj.movement_type != Default::default() {
-            node.children()
-                .push(NodeIndex::from_id(format!("#{}-mov-type:{}", subobj.name, subobj.movement_type as i32)).build(&mut self.root.nodes));
+            node.push_metadata(&mut self.nodes, &prefix, "mov-type", subobj.movement_type as i32);
         }
 
         if subobj.movement_axis != Default::default() {
-            node.children()
-                .push(NodeIndex::from_id(format!("#{}-mov-axis:{}", subobj.name, subobj.movement_axis as i32)).build(&mut self.root.nodes));
+            node.push_metadata(&mut self.nodes, &prefix, "mov-axis", subobj.movement_axis as i32);
         }
 
         node.mesh = Some(geo_id);
         for &id in &subobj.children {
-            node.children().push(
-                self.make_subobj_node(subobjs, &subobjs[id], turrets, materials)
-                    .build(&mut self.root.nodes),
-            );
+            let child = self.make_subobj_node(subobjs, &subobjs[id], turrets, materials).build(&mut self.nodes);
+            self.subobj_nodes.insert(id, child);
+            node.children().push(child);
         }
 
         node
     }
 
-    pub fn build_gltf(&mut self, model: &Model) {
-        let up = UpAxis::YUp;
-        self.root.materials.extend(model.textures.iter().map(|tex| {
-            let image = json::Image {
-                uri: Some(tex.into()),
-                buffer_view: Default::default(),
-                mime_type: Default::default(),
-                name: Default::default(),
-                extensions: Default::default(),
-                extras: Default::default(),
-            };
-            let texture = json::Texture {
-                name: Some(format!("{}-texture", tex)),
-                source: Self::push(&mut self.root.images, image),
-                sampler: Default::default(),
-                extensions: Default::default(),
-                extras: Default::default(),
-            };
-            json::Material {
+    // png and jpg files can be referred to as they are (or embedded in a glb)
+    // anything else (dds...) has to be converted to png and embedded, since glTF can't hold it
+    fn push_image(&mut self, name: String, path: &std::path::Path, binary: bool, out_dir: Option<&std::path::Path>) -> Option<Index<json::Image>> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        let mime_type = match &*ext {
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            _ => None,
+        };
+        let mut image = json::Image {
+            uri: None,
+            buffer_view: None,
+            mime_type: None,
+            name: Some(name),
+            extensions: Default::default(),
+            extras: Default::default(),
+        };
+
+        match (mime_type, binary, out_dir.and_then(|dir| relative_uri(path, dir))) {
+            (Some(_), false, Some(uri)) => image.uri = Some(uri),
+            _ => {
+                let data = std::fs::read(path).ok()?;
+                let (data, mime_type) = match mime_type {
+                    Some(mime_type) => (data, mime_type),
+                    None => {
+                        // textures which can't be converted are just left out
+                        let mut png = vec![];
+                        image::load_from_memory(&data)
+                            .ok()?
+                            .write_to(&mut io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+                            .ok()?;
+                        (png, "image/png")
+                    }
+                };
+                if binary {
+                    image.buffer_view = Some(Self::push(
+                        &mut self.root.buffer_views,
+                        json::buffer::View {
+                            buffer: json::Index::new(0),
+                            byte_length: data.len() as u32,
+                            byte_offset: Some(self.buffer.len() as u32),
+                            byte_stride: None,
+                            extensions: None,
+                            extras: Default::default(),
+                            name: None,
+                            target: None,
+                        },
+                    ));
+                    image.mime_type = Some(json::image::MimeType(mime_type.to_string()));
+                    self.buffer.extend(data);
+                    align_buf(&mut self.buffer).unwrap();
+                } else {
+                    image.uri = Some(format!("data:{};base64,{}", mime_type, base64::encode(&data)));
+                }
+            }
+        }
+        Some(Self::push(&mut self.root.images, image))
+    }
+
+    fn build_materials(&mut self, model: &Model, binary: bool, out_dir: Option<&std::path::Path>) {
+        for tex in &model.textures {
+            // fso's companion maps are found next to the texture itself, by these suffixes
+            let [base, normal, shine, glow, ao] = ["", "-normal", "-shine", "-glow", "-ao"].map(|suffix| {
+                let name = format!("{}{}", tex, suffix);
+                let path = model.find_texture_file(&name)?;
+                let source = self.push_image(name.clone(), &path, binary, out_dir)?;
+                let texture = json::Texture {
+                    name: Some(name),
+                    source,
+                    sampler: Default::default(),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                };
+                Some(json::texture::Info {
+                    index: Self::push(&mut self.root.textures, texture),
+                    tex_coord: 0,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                })
+            });
+
+            let mut material = json::Material {
                 name: Some(tex.clone()),
                 pbr_metallic_roughness: json::material::PbrMetallicRoughness {
-                    base_color_texture: Some(json::texture::Info {
-                        index: Self::push(&mut self.root.textures, texture),
-                        tex_coord: 0,
-                        extensions: Default::default(),
-                        extras: Default::default(),
-                    }),
+                    base_color_texture: base.clone(),
+                    // nothing in fso is metallic, shininess only comes across through the specular-glossiness extension
+                    metallic_factor: json::material::StrengthFactor(0.0),
                     ..Default::default()
                 },
+                normal_texture: normal.map(|info| json::material::NormalTexture {
+                    index: info.index,
+                    scale: 1.0,
+                    tex_coord: 0,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                }),
+                occlusion_texture: ao.map(|info| json::material::OcclusionTexture {
+                    index: info.index,
+                    strength: json::material::StrengthFactor(1.0),
+                    tex_coord: 0,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                }),
+                emissive_factor: json::material::EmissiveFactor(if glow.is_some() { [1.0; 3] } else { [0.0; 3] }),
+                emissive_texture: glow,
                 ..Default::default()
+            };
+
+            // shine maps are specular color + glossiness in alpha, exactly what this extension wants
+            if shine.is_some() {
+                material.extensions = Some(json::extensions::material::Material {
+                    pbr_specular_glossiness: Some(json::extensions::material::PbrSpecularGlossiness {
+                        diffuse_texture: base,
+                        specular_glossiness_texture: shine,
+                        ..Default::default()
+                    }),
+                });
+                let extension = "KHR_materials_pbrSpecularGlossiness".to_string();
+                if !self.root.extensions_used.contains(&extension) {
+                    self.root.extensions_used.push(extension);
+                }
             }
-        }));
+
+            self.root.materials.push(material);
+        }
+    }
+
+    // adds a sampler and channel to the animation rotating node through the keys, (time, pof axis, angle) triples
+    fn push_rotation_channel(&mut self, animation: &mut json::Animation, node: NodeIndex, keys: &[(f32, Vec3d, f32)]) {
+        let coords = self.options.coords(UpAxis::YUp);
+        align_buf(&mut self.buffer).unwrap();
+
+        let start = self.buffer.len();
+        for &(time, _, _) in keys {
+            time.write_to(&mut self.buffer).unwrap();
+        }
+        let view = self.push_buffer_view(start, size_of::<f32>(), true, keys.len(), None);
+        let input = self.push_accessor(view, 0, keys.len(), ComponentType::F32, json::accessor::Type::Scalar, None);
+        // times are required to have their bounds
+        let times = &mut self.root.accessors[input.value()];
+        times.min = Some(json::Value::from(vec![keys[0].0]));
+        times.max = Some(json::Value::from(vec![keys[keys.len() - 1].0]));
+
+        let start = self.buffer.len();
+        for &(_, axis, angle) in keys {
+            // pof is left handed and the file isn't, which reverses the sense of rotations
+            let axis = axis.normalize().to_coord_dir(coords);
+            let (sin_a, cos_a) = f32::sin_cos(-angle / 2.);
+            for component in [axis.x * sin_a, axis.y * sin_a, axis.z * sin_a, cos_a] {
+                self.buffer.write_f32::<LE>(component).unwrap();
+            }
+        }
+        let view = self.push_buffer_view(start, size_of::<[f32; 4]>(), true, keys.len(), None);
+        let output = self.push_accessor(view, 0, keys.len(), ComponentType::F32, json::accessor::Type::Vec4, None);
+
+        let sampler = Self::push(
+            &mut animation.samplers,
+            json::animation::Sampler {
+                extensions: None,
+                extras: Default::default(),
+                input,
+                interpolation: Valid(json::animation::Interpolation::Linear),
+                output,
+            },
+        );
+        animation.channels.push(json::animation::Channel {
+            sampler,
+            target: json::animation::Target {
+                extensions: None,
+                extras: Default::default(),
+                node,
+                path: Valid(json::animation::Property::Rotation),
+            },
+            extensions: None,
+            extras: Default::default(),
+        });
+    }
+
+    // a looping animation for each subobject spinning at its $rotate rate and each turret sweeping through its fov
+    // they're kept apart since their loops are all different lengths, viewers can generally play them all at once
+    fn build_animations(&mut self, model: &Model) {
+        const TURRET_SWEEP_TIME: f32 = 8.0;
+        const TURRET_SWEEP_KEYS: usize = 64;
+
+        let new_animation = |name: &str| json::Animation {
+            extensions: None,
+            extras: Default::default(),
+            channels: vec![],
+            name: Some(name.to_string()),
+            samplers: vec![],
+        };
+
+        for subobj in &model.sub_objects {
+            if !matches!(subobj.movement_type, SubsysMovementType::Regular | SubsysMovementType::Intrinsic)
+                || model
+                    .turrets
+                    .iter()
+                    .any(|turret| turret.base_obj == subobj.obj_id || turret.gun_obj == subobj.obj_id)
+            {
+                continue;
+            }
+            let axis = match subobj.movement_axis {
+                SubsysMovementAxis::X => Vec3d { x: 1.0, y: 0.0, z: 0.0 },
+                SubsysMovementAxis::Y => Vec3d { x: 0.0, y: 1.0, z: 0.0 },
+                SubsysMovementAxis::Z => Vec3d { x: 0.0, y: 0.0, z: 1.0 },
+                _ => continue,
+            };
+            // seconds per turn, negative to turn the other way
+            let period = crate::properties_get_field(&subobj.properties, "$rotate").and_then(|period| period.trim().parse::<f32>().ok());
+            let (period, node) = match (period, self.subobj_nodes.get(&subobj.obj_id)) {
+                (Some(period), Some(&node)) if period.is_finite() && period != 0.0 => (period, node),
+                _ => continue,
+            };
+
+            // one turn, a quarter turn per key, since a key may not be half a turn or more from the last
+            let keys = (0..=4)
+                .map(|i| (i as f32 / 4.0 * period.abs(), axis, i as f32 * PI / 2.0 * period.signum()))
+                .collect::<Vec<_>>();
+            let mut animation = new_animation(&subobj.name);
+            self.push_rotation_channel(&mut animation, node, &keys);
+            self.root.animations.push(animation);
+        }
+
+        // the turret's base turns about its normal, and its gun pitches up from facing forward towards the normal, twice as often
+        let key_time = |i: usize| i as f32 / TURRET_SWEEP_KEYS as f32;
+        for turret in &model.turrets {
+            let normal = turret.normal.0.normalize();
+            let properties = &model.sub_objects[turret.base_obj].properties;
+            let fov = |field, default: f32| {
+                let fov = crate::properties_get_field(properties, field).and_then(|fov| fov.trim().parse::<f32>().ok());
+                fov.filter(|fov| fov.is_finite()).unwrap_or(default).clamp(0.0, 360.0).to_radians()
+            };
+            let base_fov = fov("$base_fov", 360.0);
+            let fov = fov("$fov", 180.0);
+
+            let mut animation = new_animation(&model.sub_objects[turret.base_obj].name);
+
+            if let Some(&node) = self.subobj_nodes.get(&turret.base_obj) {
+                let keys = (0..=TURRET_SWEEP_KEYS)
+                    .map(|i| {
+                        let t = key_time(i);
+                        (t * TURRET_SWEEP_TIME, normal, base_fov / 2.0 * (t * 2.0 * PI).sin())
+                    })
+                    .collect::<Vec<_>>();
+                self.push_rotation_channel(&mut animation, node, &keys);
+            }
+
+            if turret.gun_obj != turret.base_obj {
+                if let Some(&node) = self.subobj_nodes.get(&turret.gun_obj) {
+                    let mut forward = Vec3d { x: 0.0, y: 0.0, z: 1.0 };
+                    if normal.cross(forward).magnitude() < 0.001 {
+                        forward = Vec3d { x: 0.0, y: 1.0, z: 0.0 };
+                    }
+                    let axis = forward.cross(normal);
+                    let (min, max) = ((PI / 2.0 - fov / 2.0).max(0.0), PI / 2.0);
+                    let keys = (0..=TURRET_SWEEP_KEYS)
+                        .map(|i| {
+                            let t = key_time(i);
+                            (t * TURRET_SWEEP_TIME, axis, min + (max - min) * (1.0 - (t * 4.0 * PI).cos()) / 2.0)
+                        })
+                        .collect::<Vec<_>>();
+                    self.push_rotation_channel(&mut animation, node, &keys);
+                }
+            }
+
+            if !animation.channels.is_empty() {
+                self.root.animations.push(animation);
+            }
+        }
+    }
+
+    pub fn build_gltf(&mut self, model: &Model, binary: bool, out_dir: Option<&std::path::Path>, metadata_in_extras: bool, animated: bool) {
+        let coords = self.options.coords(UpAxis::YUp);
+        self.nodes.metadata_in_extras = metadata_in_extras;
+        self.build_materials(model, binary, out_dir);
 
         let mut nodes = vec![];
 
@@ -1446,54 +1864,63 @@ impl GltfBuilder {
 
                 for (i, insignia) in model.insignias.iter().enumerate() {
                     if model.get_detail_level(subobj.obj_id) == Some(insignia.detail_level) {
-                        top_level_node.children().push(self.make_insignia_node(insignia, i, up))
+                        top_level_node.children().push(self.make_insignia_node(insignia, i, coords))
                     }
                 }
 
-                nodes.push(top_level_node.build(&mut self.root.nodes));
+                let top_level_node = top_level_node.build(&mut self.nodes);
+                self.subobj_nodes.insert(subobj.obj_id, top_level_node);
+                nodes.push(top_level_node);
             }
         }
 
         if let Some(shield_data) = &model.shield_data {
-            nodes.push(self.make_shield_node(shield_data, up));
+            nodes.push(self.make_shield_node(shield_data, coords));
         }
 
         if !model.thruster_banks.is_empty() {
-            nodes.push(make_thrusters_node(&mut self.root.nodes, &model.thruster_banks, up));
+            nodes.push(make_thrusters_node(&mut self.nodes, &model.thruster_banks, coords));
         }
 
         if !model.paths.is_empty() {
-            nodes.push(make_paths_node(&mut self.root.nodes, &model.paths, up));
+            nodes.push(make_paths_node(&mut self.nodes, &model.paths, &model.sub_objects, coords));
         }
 
         if !model.primary_weps.is_empty() {
-            nodes.push(make_weapons_node(&mut self.root.nodes, &model.primary_weps, "primary", up));
+            nodes.push(make_weapons_node(&mut self.nodes, &model.primary_weps, "primary", coords));
         }
 
         if !model.secondary_weps.is_empty() {
-            nodes.push(make_weapons_node(&mut self.root.nodes, &model.secondary_weps, "secondary", up));
+            nodes.push(make_weapons_node(&mut self.nodes, &model.secondary_weps, "secondary", coords));
         }
 
         if !model.docking_bays.is_empty() {
-            nodes.push(make_docking_bays_node(&mut self.root.nodes, &model.docking_bays, up));
+            nodes.push(make_docking_bays_node(&mut self.nodes, &model.docking_bays, coords));
         }
 
         if !model.glow_banks.is_empty() {
-            nodes.push(make_glows_node(&mut self.root.nodes, &model.glow_banks, up));
+            nodes.push(make_glows_node(&mut self.nodes, &model.glow_banks, coords));
         }
 
         if !model.special_points.is_empty() {
-            nodes.push(make_specials_node(&mut self.root.nodes, &model.special_points, up));
+            nodes.push(make_specials_node(&mut self.nodes, &model.special_points, coords));
         }
 
         if !model.eye_points.is_empty() {
-            nodes.push(make_eyes_node(&mut self.root.nodes, &model.eye_points, up));
+            nodes.push(make_eyes_node(&mut self.nodes, &model.eye_points, coords));
         }
 
         if !model.visual_center.is_null() {
-            nodes.push(make_visual_center_node(&mut self.root.nodes, &model.visual_center, up));
+            nodes.push(make_visual_center_node(&mut self.nodes, &model.visual_center, coords));
         }
 
+        nodes.push(make_header_node(&mut self.nodes, model));
+
+        if animated {
+            self.build_animations(model);
+        }
+
+        self.root.nodes = std::mem::take(&mut self.nodes.nodes);
         self.root.scene = Some(GltfBuilder::push(
             &mut self.root.scenes,
             json::Scene {
@@ -1515,9 +1942,16 @@ impl GltfBuilder {
 }
 
 impl Model {
-    pub fn write_gltf(&self, writer: impl Write, binary: bool) -> Result<(), gltf::Error> {
-        let mut builder = GltfBuilder::default();
-        builder.build_gltf(self);
+    // textures are embedded in a glb, otherwise they're referred to relative to out_dir, the folder the gltf is being written to
+    // (or embedded as well, if that isn't known)
+    // metadata_in_extras puts properties and such in the nodes' extras, instead of the name-encoded helper nodes shared with dae
+    // glTF is always y up, but the options can say otherwise for programs which don't follow that, see write_dae
+    // animated adds looping animations of the spinning subobjects and turrets, for previewing in a glTF viewer
+    pub fn write_gltf(
+        &self, writer: impl Write, binary: bool, out_dir: Option<&std::path::Path>, metadata_in_extras: bool, animated: bool, options: &ImportOptions,
+    ) -> Result<(), gltf::Error> {
+        let mut builder = GltfBuilder { options: *options, ..Default::default() };
+        builder.build_gltf(self, binary, out_dir, metadata_in_extras, animated);
         if binary {
             let json_string = json::serialize::to_string(&builder.root)?;
             let mut json_offset = json_string.len() as u32;
@@ -1543,3 +1977,376 @@ impl Model {
         }
     }
 }
+
+// ==============================================================================
+// OBJ Writing
+// ==============================================================================
+
+// escapes newlines so that multi-line text fits on one line of the metadata file, or in one helper node's name
+fn escape_metadata(val: &str) -> String {
+    val.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+impl Model {
+    // OBJ only holds geometry, so everything else has to go in a separate metadata file, see write_obj_metadata
+    // positions are written in model space, so that the pieces line up in other programs
+    // the options are applied to the geometry only, the metadata is in pof coordinates regardless
+    pub fn write_obj(&self, w: &mut impl Write, mtl_file_name: &str, options: &ImportOptions) -> io::Result<()> {
+        let coords = options.coords(UpAxis::YUp);
+        writeln!(w, "mtllib {}", mtl_file_name)?;
+
+        // indices are 1-based, and count up over the whole file
+        let (mut vert_base, mut uv_base, mut norm_base) = (1, 1, 1);
+        for subobj in &self.sub_objects {
+            writeln!(w, "o {}", subobj.name)?;
+            let offset = self.get_total_subobj_offset(subobj.obj_id);
+            for vert in &subobj.bsp_data.verts {
+                let vert = (*vert + offset).to_coord(coords);
+                writeln!(w, "v {} {} {}", vert.x, vert.y, vert.z)?;
+            }
+            for norm in &subobj.bsp_data.norms {
+                let norm = norm.to_coord_dir(coords);
+                writeln!(w, "vn {} {} {}", norm.x, norm.y, norm.z)?;
+            }
+            let mut uv_count = 0;
+            for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
+                for vert in &poly.verts {
+                    writeln!(w, "vt {} {}", vert.uv.0, 1. - vert.uv.1)?;
+                }
+            }
+            let mut texture = None;
+            for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
+                if texture != Some(poly.texture) {
+                    texture = Some(poly.texture);
+                    writeln!(w, "usemtl {}", self.textures[poly.texture.0 as usize])?;
+                }
+                write!(w, "f")?;
+                // normal facing inverted, same as dae
+                for (i, vert) in wind_polygon(coords, &poly.verts.iter().enumerate().collect::<Vec<_>>()) {
+                    write!(w, " {}/{}/{}", vert_base + vert.vertex_id.0 as usize, uv_base + uv_count + i, norm_base + vert.normal_id.0 as usize)?;
+                }
+                writeln!(w)?;
+                uv_count += poly.verts.len();
+            }
+            vert_base += subobj.bsp_data.verts.len();
+            norm_base += subobj.bsp_data.norms.len();
+            uv_base += uv_count;
+        }
+
+        for (i, insignia) in self.insignias.iter().enumerate() {
+            writeln!(w, "o insig{}", i)?;
+            for vert in &insignia.vertices {
+                let vert = (*vert + insignia.offset).to_coord(coords);
+                writeln!(w, "v {} {} {}", vert.x, vert.y, vert.z)?;
+            }
+            for (polyvert1, polyvert2, polyvert3) in &insignia.faces {
+                for vert in [polyvert1, polyvert2, polyvert3] {
+                    writeln!(w, "vt {} {}", vert.uv.0, 1. - vert.uv.1)?;
+                }
+            }
+            for (j, (polyvert1, polyvert2, polyvert3)) in insignia.faces.iter().enumerate() {
+                write!(w, "f")?;
+                for (k, polyvert) in wind_triangle(coords, [(0, polyvert1), (1, polyvert2), (2, polyvert3)]) {
+                    write!(w, " {}/{}", vert_base + polyvert.vertex_id.0 as usize, uv_base + 3 * j + k)?;
+                }
+                writeln!(w)?;
+            }
+            vert_base += insignia.vertices.len();
+            uv_base += 3 * insignia.faces.len();
+        }
+
+        if let Some(shield) = &self.shield_data {
+            writeln!(w, "o shield")?;
+            for vert in &shield.verts {
+                let vert = vert.to_coord(coords);
+                writeln!(w, "v {} {} {}", vert.x, vert.y, vert.z)?;
+            }
+            for poly in &shield.polygons {
+                let (vert1, vert2, vert3) = poly.verts;
+                let [vert1, vert2, vert3] = wind_triangle(coords, [vert1, vert2, vert3]).map(|vert| vert_base + vert.0 as usize);
+                writeln!(w, "f {} {} {}", vert1, vert2, vert3)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_mtl(&self, w: &mut impl Write) -> io::Result<()> {
+        for texture in &self.textures {
+            writeln!(w, "newmtl {}", texture)?;
+            writeln!(w, "Kd 1 1 1")?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    // everything an OBJ file can't hold, one entry per line, with the names of things at the end of their lines
+    // subobjects, turrets etc. are referred to by name, so that they still match up if the OBJ is edited in between
+    // the entries below an entry (position, properties...) belong to it, everything is in pof coordinates
+    pub fn write_obj_metadata(&self, w: &mut impl Write) -> io::Result<()> {
+        fn vec(v: &Vec3d) -> String {
+            format!("{} {} {}", v.x, v.y, v.z)
+        }
+        fn properties(w: &mut impl Write, properties: &str) -> io::Result<()> {
+            if !properties.is_empty() {
+                writeln!(w, "properties {}", escape_metadata(properties))?;
+            }
+            Ok(())
+        }
+        let name = |id: ObjectId| &self.sub_objects[id].name;
+
+        writeln!(w, "# pof-tools metadata")?;
+        for subobj in &self.sub_objects {
+            writeln!(w, "subobject {}", subobj.name)?;
+            if let Some(parent) = subobj.parent {
+                writeln!(w, "parent {}", name(parent))?;
+            }
+            writeln!(w, "offset {}", vec(&subobj.offset))?;
+            if subobj.movement_type != SubsysMovementType::None || subobj.movement_axis != SubsysMovementAxis::None {
+                writeln!(w, "movement {} {}", i32::from(subobj.movement_type), i32::from(subobj.movement_axis))?;
+            }
+            if subobj.is_debris_model {
+                writeln!(w, "debris")?;
+            }
+            properties(w, &subobj.properties)?;
+        }
+
+        for (i, insignia) in self.insignias.iter().enumerate() {
+            writeln!(w, "insignia insig{}", i)?;
+            writeln!(w, "detail_level {}", insignia.detail_level)?;
+            writeln!(w, "offset {}", vec(&insignia.offset))?;
+        }
+
+        for turret in &self.turrets {
+            writeln!(w, "turret {}", name(turret.base_obj))?;
+            writeln!(w, "gun {}", name(turret.gun_obj))?;
+            writeln!(w, "normal {}", vec(&turret.normal.0))?;
+            for point in &turret.fire_points {
+                writeln!(w, "point {}", vec(point))?;
+            }
+        }
+
+        for bank in &self.thruster_banks {
+            writeln!(w, "thruster_bank")?;
+            properties(w, &bank.properties)?;
+            for glow in &bank.glows {
+                writeln!(w, "glow {} {} {}", vec(&glow.position), vec(&glow.normal), glow.radius)?;
+            }
+        }
+
+        for (keyword, banks) in [("primary_bank", &self.primary_weps), ("secondary_bank", &self.secondary_weps)] {
+            for bank in banks {
+                writeln!(w, "{}", keyword)?;
+                for hardpoint in bank {
+                    writeln!(w, "hardpoint {} {} {}", vec(&hardpoint.position), vec(&hardpoint.normal.0), hardpoint.offset)?;
+                }
+            }
+        }
+
+        for dock in &self.docking_bays {
+            writeln!(w, "dock")?;
+            writeln!(w, "position {}", vec(&dock.position))?;
+            writeln!(w, "fvec {}", vec(&dock.fvec.0))?;
+            writeln!(w, "uvec {}", vec(&dock.uvec.0))?;
+            if let Some(path) = dock.path {
+                writeln!(w, "dock_path {}", path.0)?;
+            }
+            properties(w, &dock.properties)?;
+        }
+
+        for bank in &self.glow_banks {
+            writeln!(w, "glow_bank {}", name(bank.obj_parent))?;
+            writeln!(w, "timing {} {} {}", bank.disp_time, bank.on_time, bank.off_time)?;
+            writeln!(w, "lod {}", bank.lod)?;
+            writeln!(w, "type {}", bank.glow_type)?;
+            properties(w, &bank.properties)?;
+            for glow in &bank.glow_points {
+                writeln!(w, "glow {} {} {}", vec(&glow.position), vec(&glow.normal), glow.radius)?;
+            }
+        }
+
+        for point in &self.special_points {
+            writeln!(w, "special_point {}", point.name)?;
+            writeln!(w, "position {}", vec(&point.position))?;
+            writeln!(w, "radius {}", point.radius)?;
+            properties(w, &point.properties)?;
+        }
+
+        for eye in &self.eye_points {
+            writeln!(w, "eye_point {}", name(eye.attached_subobj))?;
+            writeln!(w, "position {}", vec(&eye.offset))?;
+            writeln!(w, "normal {}", vec(&eye.normal.0))?;
+        }
+
+        for path in &self.paths {
+            writeln!(w, "path {}", path.name)?;
+            if !path.parent.is_empty() {
+                writeln!(w, "parent {}", path.parent)?;
+            }
+            for point in &path.points {
+                writeln!(w, "point {} {}", vec(&point.position), point.radius)?;
+                for &turret in &point.turrets {
+                    writeln!(w, "point_turret {}", name(turret))?;
+                }
+            }
+        }
+
+        writeln!(w, "visual_center {}", vec(&self.visual_center))?;
+        if !self.comments.is_empty() {
+            writeln!(w, "comments {}", escape_metadata(&self.comments))?;
+        }
+        Ok(())
+    }
+}
+
+// ==============================================================================
+// STL / PLY Writing
+// ==============================================================================
+
+// a flat triangle mesh, already swizzled into the z-up coordinates that printing and analysis tools expect
+#[derive(Default)]
+struct TriMesh {
+    verts: Vec<Vec3d>,
+    tris: Vec<[u32; 3]>,
+}
+
+impl TriMesh {
+    fn write_stl(&self, w: &mut impl Write, name: &str, binary: bool) -> io::Result<()> {
+        let facet_normal = |[v1, v2, v3]: [Vec3d; 3]| {
+            let normal = (v2 - v1).cross(v3 - v1);
+            if normal.is_null() {
+                normal
+            } else {
+                normal.normalize()
+            }
+        };
+        let tris = self.tris.iter().map(|tri| tri.map(|i| self.verts[i as usize]));
+
+        if binary {
+            let mut header = [0_u8; 80];
+            let name = name.as_bytes();
+            let len = name.len().min(header.len());
+            header[..len].copy_from_slice(&name[..len]);
+            w.write_all(&header)?;
+            (self.tris.len() as u32).write_to(w)?;
+            for tri in tris {
+                facet_normal(tri).write_to(w)?;
+                for vert in tri {
+                    vert.write_to(w)?;
+                }
+                // attribute byte count, which nothing uses
+                0_u16.write_to(w)?;
+            }
+        } else {
+            writeln!(w, "solid {}", name)?;
+            for tri in tris {
+                let normal = facet_normal(tri);
+                writeln!(w, "facet normal {} {} {}", normal.x, normal.y, normal.z)?;
+                writeln!(w, "  outer loop")?;
+                for vert in tri {
+                    writeln!(w, "    vertex {} {} {}", vert.x, vert.y, vert.z)?;
+                }
+                writeln!(w, "  endloop")?;
+                writeln!(w, "endfacet")?;
+            }
+            writeln!(w, "endsolid {}", name)?;
+        }
+        Ok(())
+    }
+
+    // unlike STL, PLY keeps shared vertices, so the mesh's topology survives
+    fn write_ply(&self, w: &mut impl Write, binary: bool) -> io::Result<()> {
+        writeln!(w, "ply")?;
+        writeln!(w, "format {} 1.0", if binary { "binary_little_endian" } else { "ascii" })?;
+        writeln!(w, "element vertex {}", self.verts.len())?;
+        writeln!(w, "property float x")?;
+        writeln!(w, "property float y")?;
+        writeln!(w, "property float z")?;
+        writeln!(w, "element face {}", self.tris.len())?;
+        writeln!(w, "property list uchar uint vertex_indices")?;
+        writeln!(w, "end_header")?;
+        if binary {
+            for vert in &self.verts {
+                vert.write_to(w)?;
+            }
+            for tri in &self.tris {
+                3_u8.write_to(w)?;
+                for i in tri {
+                    i.write_to(w)?;
+                }
+            }
+        } else {
+            for vert in &self.verts {
+                writeln!(w, "{} {} {}", vert.x, vert.y, vert.z)?;
+            }
+            for [v1, v2, v3] in &self.tris {
+                writeln!(w, "3 {} {} {}", v1, v2, v3)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Model {
+    fn detail_level_root(&self, detail_level: usize) -> io::Result<ObjectId> {
+        self.header
+            .detail_levels
+            .get(detail_level)
+            .copied()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("the model has no detail level {}", detail_level)))
+    }
+
+    // everything in the detail level's subobject tree, in model space and triangulated
+    fn detail_level_tri_mesh(&self, root: ObjectId) -> TriMesh {
+        let mut mesh = TriMesh::default();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            let subobj = &self.sub_objects[id];
+            let offset = self.get_total_subobj_offset(id);
+            let base = mesh.verts.len() as u32;
+            mesh.verts
+                .extend(subobj.bsp_data.verts.iter().map(|vert| (*vert + offset).to_coord(UpAxis::ZUp.into())));
+            for (_, poly) in subobj.bsp_data.collision_tree.leaves() {
+                if let [vert1, rest @ ..] = &*poly.verts {
+                    for verts in rest.windows(2) {
+                        // intentional swizzle, the z-up swap flips handedness
+                        mesh.tris.push([vert1, &verts[1], &verts[0]].map(|vert| base + vert.vertex_id.0));
+                    }
+                }
+            }
+            stack.extend(subobj.children.iter().copied());
+        }
+        mesh
+    }
+
+    pub fn write_stl(&self, w: &mut impl Write, detail_level: usize, binary: bool) -> io::Result<()> {
+        let root = self.detail_level_root(detail_level)?;
+        self.detail_level_tri_mesh(root).write_stl(w, &self.sub_objects[root].name, binary)
+    }
+
+    pub fn write_ply(&self, w: &mut impl Write, detail_level: usize, binary: bool) -> io::Result<()> {
+        self.detail_level_tri_mesh(self.detail_level_root(detail_level)?).write_ply(w, binary)
+    }
+}
+
+impl ShieldData {
+    fn tri_mesh(&self) -> TriMesh {
+        TriMesh {
+            verts: self.verts.iter().map(|vert| vert.to_coord(UpAxis::ZUp.into())).collect(),
+            // intentional swizzle
+            tris: self
+                .polygons
+                .iter()
+                .map(|poly| [poly.verts.0 .0, poly.verts.2 .0, poly.verts.1 .0])
+                .collect(),
+        }
+    }
+
+    pub fn write_stl(&self, w: &mut impl Write, binary: bool) -> io::Result<()> {
+        self.tri_mesh().write_stl(w, "shield", binary)
+    }
+
+    pub fn write_ply(&self, w: &mut impl Write, binary: bool) -> io::Result<()> {
+        self.tri_mesh().write_ply(w, binary)
+    }
+}
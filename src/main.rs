@@ -19,7 +19,10 @@ use glium::{
 };
 use glm::Mat4x4;
 use native_dialog::FileDialog;
-use pof::{BspData, Insignia, Model, NormalId, ObjVec, ObjectId, Parser, PolyVertex, Polygon, ShieldData, SubObject, TextureId, Vec3d, VertexId};
+use pof::{
+    BspData, ImportError, ImportOptions, ImportWarning, Insignia, Model, NormalId, ObjVec, ObjectId, Parser, PolyVertex, Polygon, ShieldData,
+    SubObject, TextureId, Vec3d, VertexId,
+};
 use simplelog::*;
 use std::{
     collections::HashMap,
@@ -348,26 +351,69 @@ pub struct Normal {
 glium::implement_vertex!(Normal, normal);
 
 impl PofToolsGui {
-    fn save_model(model: &Model) -> Option<String> {
+    fn save_model(model: &Model, options: &ImportOptions, animate_gltf: bool, metadata_in_extras: bool) -> Option<String> {
         let mut out = None;
         // use a scoped thread here, its ok to block the main window for now i guess
         crossbeam::thread::scope(|s| {
             s.spawn(|_| {
                 let path = FileDialog::new()
                     .set_filename(&model.path_to_file.file_name().unwrap_or_default().to_string_lossy())
-                    .add_filter("All Supported Files", &["pof", "dae", "gltf", "glb"])
+                    .add_filter("All Supported Files", &["pof", "dae", "gltf", "glb", "obj", "stl", "ply"])
                     .add_filter("Parallax Object File", &["pof"])
                     .add_filter("Digital Asset Exchange file", &["dae"])
                     .add_filter("GL Transmission Format (Embedded)", &["gltf"])
                     .add_filter("GL Transmission Format (Binary)", &["glb"])
+                    .add_filter("Wavefront OBJ", &["obj"])
+                    .add_filter("Stereolithography (detail0 only)", &["stl"])
+                    .add_filter("Polygon File Format (detail0 only)", &["ply"])
                     .show_save_single_file();
                 if let Ok(Some(path)) = path {
                     let mut file = File::create(path.clone()).unwrap();
                     match path.extension() {
-                        Some(s) if s == "glb" => model.write_gltf(&mut file, true).unwrap(),
-                        Some(s) if s == "gltf" => model.write_gltf(&mut file, false).unwrap(),
-                        Some(s) if s == "dae" => model.write_dae(&mut file).unwrap(),
+                        Some(s) if s == "glb" => model
+                            .write_gltf(&mut file, true, None, metadata_in_extras, animate_gltf, options)
+                            .unwrap(),
+                        Some(s) if s == "gltf" => model
+                            .write_gltf(&mut file, false, path.parent(), metadata_in_extras, animate_gltf, options)
+                            .unwrap(),
+                        Some(s) if s == "dae" => model.write_dae(&mut file, options).unwrap(),
                         Some(s) if s == "pof" => model.write(&mut file).unwrap(),
+                        Some(s) if s == "obj" => {
+                            // the materials and everything else OBJ can't hold go in files next to it
+                            let mtl_path = path.with_extension("mtl");
+                            let mtl_name = mtl_path.file_name().unwrap_or_default().to_string_lossy();
+                            model.write_obj(&mut file, &mtl_name, options).unwrap();
+                            model.write_mtl(&mut File::create(&mtl_path).unwrap()).unwrap();
+                            model
+                                .write_obj_metadata(&mut File::create(path.with_extension(pof::OBJ_METADATA_EXTENSION)).unwrap())
+                                .unwrap();
+                        }
+                        Some(s) if s == "stl" || s == "ply" => {
+                            let is_stl = s == "stl";
+                            // a model can be just a shield or insignia, with no detail levels to write
+                            if !model.header.detail_levels.is_empty() {
+                                if is_stl {
+                                    model.write_stl(&mut file, 0, true).unwrap();
+                                } else {
+                                    model.write_ply(&mut file, 0, true).unwrap();
+                                }
+                            }
+                            // the shield goes in its own file next to it
+                            if let Some(shield) = &model.shield_data {
+                                let mut shield_path = path.clone();
+                                shield_path.set_file_name(format!(
+                                    "{}-shield.{}",
+                                    path.file_stem().unwrap_or_default().to_string_lossy(),
+                                    s.to_string_lossy()
+                                ));
+                                let mut shield_file = File::create(shield_path).unwrap();
+                                if is_stl {
+                                    shield.write_stl(&mut shield_file, true).unwrap();
+                                } else {
+                                    shield.write_ply(&mut shield_file, true).unwrap();
+                                }
+                            }
+                        }
                         s => panic!("unexpected extension {:?}", s),
                     }
                     out = Some(path.file_name().and_then(|f| f.to_str()).unwrap_or("").to_string());
@@ -378,9 +424,44 @@ impl PofToolsGui {
         out
     }
 
+    // logs any warnings or the error from importing a file, returning whatever was imported
+    fn log_import_result<T>(filename: &str, result: Result<(T, Vec<ImportWarning>), ImportError>) -> Option<T> {
+        match result {
+            Ok((imported, warnings)) => {
+                for warning in warnings {
+                    warn!("{}: {}", filename, warning);
+                }
+                Some(imported)
+            }
+            Err(err) => {
+                error!("Failed to load {}: {}", filename, err);
+                None
+            }
+        }
+    }
+
+    // asks for a DAE, glTF or OBJ file to pull a single subobject's geometry from
+    fn pick_geometry_file() -> Option<PathBuf> {
+        let mut out = None;
+        crossbeam::thread::scope(|s| {
+            s.spawn(|_| {
+                out = FileDialog::new()
+                    .add_filter("All supported files", &["dae", "gltf", "glb", "obj"])
+                    .add_filter("COLLADA", &["dae"])
+                    .add_filter("GL Transmission Format", &["gltf", "glb"])
+                    .add_filter("Wavefront OBJ", &["obj"])
+                    .show_open_single_file()
+                    .unwrap_or_default();
+            });
+        })
+        .unwrap();
+        out
+    }
+
     // opens a thread which opens the dialog and starts parsing a model
     fn start_loading_model(&mut self, filepath: Option<PathBuf>) {
         let (sender, receiver) = std::sync::mpsc::channel();
+        let options = self.import_options;
         self.model_loading_thread = Some(receiver);
 
         // the model loading thread
@@ -388,25 +469,27 @@ impl PofToolsGui {
             let model = std::panic::catch_unwind(move || {
                 let path = filepath.or_else(|| {
                     FileDialog::new()
-                        .add_filter("All supported files", &["pof", "dae", "gltf", "glb"])
+                        .add_filter("All supported files", &["pof", "dae", "gltf", "glb", "obj"])
                         .add_filter("COLLADA", &["dae"])
                         .add_filter("Parallax Object File", &["pof"])
                         .add_filter("GL Transmission Format", &["gltf", "glb"])
+                        .add_filter("Wavefront OBJ", &["obj"])
                         .show_open_single_file()
                         .unwrap()
                 });
 
-                path.map(|path| {
+                path.and_then(|path| {
                     let ext = path.extension().map(|ext| ext.to_ascii_lowercase());
                     let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("").to_string();
                     info!("Attempting to load {}", filename);
                     match ext.as_ref().and_then(|ext| ext.to_str()) {
-                        Some("dae") => pof::parse_dae(path),
-                        Some("gltf" | "glb") => pof::parse_gltf(path),
+                        Some("dae") => PofToolsGui::log_import_result(&filename, pof::parse_dae(path, &options)).map(Box::new),
+                        Some("gltf" | "glb") => PofToolsGui::log_import_result(&filename, pof::parse_gltf(path, &options)).map(Box::new),
+                        Some("obj") => PofToolsGui::log_import_result(&filename, pof::parse_obj(path, &options)).map(Box::new),
                         Some("pof") => {
                             let file = File::open(&path).expect("TODO invalid file or smth i dunno");
                             let mut parser = Parser::new(file).expect("TODO invalid version of file or smth i dunno");
-                            Box::new(parser.parse(path).expect("TODO invalid pof file or smth i dunno"))
+                            Some(Box::new(parser.parse(path).expect("TODO invalid pof file or smth i dunno")))
                         }
                         _ => todo!(),
                     }
@@ -496,22 +579,20 @@ impl PofToolsGui {
         self.buffer_textures.clear();
         let (sender, receiver) = std::sync::mpsc::channel();
         self.texture_loading_thread = Some(receiver);
-        let textures = self.model.textures.clone();
-        let path = self.model.path_to_file.clone();
+        let textures = self
+            .model
+            .textures
+            .iter()
+            .map(|tex_name| (tex_name.clone(), self.model.find_texture_file(tex_name)))
+            .collect::<Vec<_>>();
 
         // the texture loading thread
         std::thread::spawn(move || {
-            for (i, tex_name) in textures.iter().enumerate() {
-                if let Some((mut file, format)) = ["png", "dds"].iter().find_map(|ext| {
-                    if let Ok(file) = std::fs::File::open(path.with_file_name(format!("{}.{}", tex_name, ext))) {
-                        Some((file, image::ImageFormat::from_extension(ext).unwrap()))
-                    } else {
-                        Some((
-                            std::fs::File::open(path.with_file_name(format!("../maps/{}.{}", tex_name, ext))).ok()?,
-                            image::ImageFormat::from_extension(ext).unwrap(),
-                        ))
-                    }
-                }) {
+            for (i, (tex_name, path)) in textures.iter().enumerate() {
+                if let Some((mut file, format)) = path
+                    .as_ref()
+                    .and_then(|path| Some((std::fs::File::open(path).ok()?, image::ImageFormat::from_path(path).ok()?)))
+                {
                     let mut buf = vec![];
                     if let Err(e) = file.read_to_end(&mut buf) {
                         error!("Failed to load texture {}.{}: {:?}", tex_name, format.extensions_str()[0], e);
@@ -544,6 +625,11 @@ impl PofToolsGui {
                 *buf = GlObjectBuffers::new(display, &self.model.sub_objects[buf.obj_id], self.model.textures.len());
             }
         }
+
+        // any newly created subobjects won't have buffers yet
+        for subobj in self.model.sub_objects.iter().skip(self.buffer_objects.len()) {
+            self.buffer_objects.push(GlObjectBuffers::new(display, subobj, self.model.textures.len()));
+        }
     }
 }
 
@@ -1054,6 +1140,24 @@ fn main() {
                         }
                     }
 
+                    // draw wireframe cross sections, as circles around the z axis
+                    if pt_gui.display_cross_sections {
+                        for &(depth, radius) in &pt_gui.model.header.cross_sections {
+                            let mut mat = glm::scaling(&glm::vec3(radius, radius, radius));
+                            mat.append_translation_mut(&glm::vec3(0.0, 0.0, depth));
+                            let matrix = view_mat * mat;
+                            let vert_matrix: [[f32; 4]; 4] = (perspective_matrix * matrix).into();
+
+                            let uniforms = glium::uniform! {
+                                vert_matrix: vert_matrix
+                            };
+
+                            target
+                                .draw(&circle_verts, &circle_indices, &wireframe_shader, &uniforms, &wireframe_params)
+                                .unwrap();
+                        }
+                    }
+
                     // don't display lollipops if you're in header or subobjects, unless display_origin is on, since that's the only lollipop they have
                     let display_lollipops = (!matches!(pt_gui.ui_state.tree_view_selection, TreeSelection::Header)
                         && !matches!(pt_gui.ui_state.tree_view_selection, TreeSelection::SubObjects(_)))
@@ -3,7 +3,7 @@ use glium::{
     texture::{RawImage2d, SrgbTexture2d},
     Display,
 };
-use pof::{BoundingBox, Model, SubObject, TextureId, Vec3d, Version};
+use pof::{BoundingBox, ImportOptions, Model, SignedAxis, SubObject, TextureId, Vec3d, Version};
 use std::{
     collections::{BTreeSet, HashMap},
     sync::mpsc::Receiver,
@@ -337,6 +337,14 @@ impl std::fmt::Display for TextureSelection {
         }
     }
 }
+impl TextureSelection {
+    pub fn texture(texture: Option<usize>) -> Self {
+        match texture {
+            Some(texture) => Self::Texture(TextureId(texture as u32)),
+            None => Self::Header,
+        }
+    }
+}
 
 #[derive(PartialEq, Hash, Debug, Clone, Copy)]
 pub(crate) enum ThrusterSelection {
@@ -410,6 +418,7 @@ pub(crate) enum Warning {
     WeaponOffsetInvalidVersion(WeaponSelection),
     InvertedBBox(Option<ObjectId>),
     UntexturedPolygons,
+    VertexExtrasNotSaved,
     // path with no parent
     // thruster with no engine subsys
     // turret uvec != turret normal
@@ -434,7 +443,11 @@ pub(crate) struct UiState {
     pub display_radius: bool,
     pub display_bbox: bool,
     pub display_origin: bool,
+    pub display_cross_sections: bool,
     pub move_only_offset: bool,
+    pub import_options: ImportOptions, // for reading and writing DAE, glTF and OBJ files
+    pub animate_gltf: bool,            // whether saved glTF files get an animation of the moving subobjects
+    pub gltf_metadata_in_extras: bool, // whether saved glTF files keep metadata in extras rather than name-encoded helper nodes
 }
 
 pub(crate) struct PofToolsGui {
@@ -567,7 +580,12 @@ impl PofToolsGui {
                 {
                     self.model.clean_up();
 
-                    let new_filename = PofToolsGui::save_model(&self.model);
+                    let new_filename = PofToolsGui::save_model(
+                        &self.model,
+                        &self.ui_state.import_options,
+                        self.ui_state.animate_gltf,
+                        self.ui_state.gltf_metadata_in_extras,
+                    );
                     if let Some(filename) = new_filename {
                         display
                             .gl_window()
@@ -576,6 +594,41 @@ impl PofToolsGui {
                     }
                 }
 
+                ui.menu_button(RichText::new("Axes & Scale").text_style(TextStyle::Button), |ui| {
+                    ui.label("For opening and saving DAE, glTF and OBJ files");
+                    let options = &mut self.ui_state.import_options;
+
+                    for (label, axis, default) in [
+                        ("Up:", &mut options.up_axis, "From file"),
+                        ("Forward:", &mut options.forward_axis, "Usual for up"),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            ui.radio_value(axis, None, default);
+                            for signed_axis in SignedAxis::ALL {
+                                ui.radio_value(axis, Some(signed_axis), signed_axis.to_string());
+                            }
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Scale:");
+                        ui.add(egui::DragValue::new(&mut options.scale).speed(0.01).clamp_range(0.0001..=10000.0))
+                            .on_hover_text("Meters per unit of the file, e.g. 0.01 for centimeters");
+                    });
+
+                    ui.checkbox(&mut options.flip_winding, "Flip winding")
+                        .on_hover_text("For files whose polygons come out inside out");
+
+                    ui.separator();
+                    ui.checkbox(&mut self.ui_state.animate_gltf, "Animate glTF")
+                        .on_hover_text("Saved glTF files spin subobjects at their $rotate rate and sweep turrets through their FOV");
+                    ui.checkbox(&mut self.ui_state.gltf_metadata_in_extras, "glTF metadata in extras")
+                        .on_hover_text(
+                            "Saved glTF files keep properties and such in node extras, instead of the helper nodes older versions of pof-tools read",
+                        );
+                });
+
                 ui.separator();
 
                 ui.menu_button(RichText::new(format!("Version: {}", self.model.version)).text_style(TextStyle::Button), |ui| {
@@ -771,6 +824,10 @@ impl PofToolsGui {
                                     );
                                     ui.add(Label::new(RichText::new(str).text_style(TextStyle::Button).color(Color32::YELLOW)));
                                 }
+                                Warning::VertexExtrasNotSaved => {
+                                    let str = format!("⚠ This model's extra UV sets and vertex colors will be dropped if saved as a POF");
+                                    ui.add(Label::new(RichText::new(str).text_style(TextStyle::Button).color(Color32::YELLOW)));
+                                }
                             }
                         }
                     });
@@ -1109,6 +1166,7 @@ impl PofToolsGui {
         self.display_bbox = false;
         self.display_radius = false;
         self.display_origin = false;
+        self.display_cross_sections = false;
 
         egui::SidePanel::right("properties_panel")
             .resizable(true)
@@ -1220,6 +1278,7 @@ impl PofToolsGui {
                     }
                 }
                 Warning::UntexturedPolygons => model.untextured_idx.is_some(),
+                Warning::VertexExtrasNotSaved => PofToolsGui::has_vertex_extras(model),
             };
 
             let existing_warning = warnings.contains(&warning);
@@ -1292,9 +1351,17 @@ impl PofToolsGui {
             if model.untextured_idx.is_some() {
                 warnings.insert(Warning::UntexturedPolygons);
             }
+
+            if PofToolsGui::has_vertex_extras(model) {
+                warnings.insert(Warning::VertexExtrasNotSaved);
+            }
         }
     }
 
+    fn has_vertex_extras(model: &Model) -> bool {
+        model.sub_objects.iter().any(|subobj| subobj.bsp_data.extras.is_some())
+    }
+
     // tests if the radius for a subobject or the header is too small for its geometry
     // None means the header/entire model's radius
     fn radius_test_failed(model: &Model, subobj_opt: Option<ObjectId>) -> bool {
@@ -4,14 +4,32 @@ use egui::{style::Widgets, text::LayoutJob, CollapsingHeader, Color32, DragValue
 use glium::Display;
 use nalgebra_glm::TMat4;
 use pof::{
-    Dock, EyePoint, GlowPoint, GlowPointBank, Insignia, Model, ObjectId, PathId, PathPoint, SpecialPoint, SubsysMovementAxis, SubsysMovementType,
-    ThrusterGlow, Vec3d, WeaponHardpoint,
+    CenterOfMassMode, Dock, EyePoint, GlowPoint, GlowPointBank, Insignia, Model, ObjectId, PathId, PathPoint, ShieldData, ShieldGenerationOptions,
+    ShieldShape, SpecialPoint, SubsysMovementAxis, SubsysMovementType, TextureId, ThrusterGlow, Vec3d, WeaponHardpoint,
 };
 
 use crate::ui::{
     DockingSelection, Error, EyeSelection, GlowSelection, InsigniaSelection, PathSelection, PofToolsGui, Set::*, SpecialPointSelection,
     SubObjectSelection, TextureSelection, ThrusterSelection, TreeSelection, TurretSelection, UiState, Warning, WeaponSelection,
 };
+use crate::GlBufferedShield;
+
+// how far (in meters) a vertex can stray from its polygon's plane before the polygon is considered non-planar
+const NON_PLANAR_TOLERANCE: f32 = 0.001;
+
+// in degrees
+const DEFAULT_SMOOTHING_ANGLE: f32 = 30.0;
+
+const DEFAULT_DENSITY: f32 = 1.0;
+
+const DEFAULT_NUM_CROSS_SECTIONS: usize = 5;
+
+const DEFAULT_MAX_HOLE_EDGES: usize = 8;
+
+// fractions of detail0's triangles
+const DEFAULT_LOD_RATIOS: &str = "0.5, 0.25, 0.125";
+
+const DEFAULT_NUM_DEBRIS: usize = 8;
 
 enum IndexingButtonsResponse {
     Switch(usize),
@@ -49,6 +67,24 @@ impl IndexingButtonsResponse {
             }
         }
     }
+
+    // like apply, but deletion goes through the given model function instead, so anything referring to the deleted item can be fixed up
+    fn apply_to_model<T: Clone + Default>(
+        self, model: &mut Model, data_vec: fn(&mut Model) -> &mut Vec<T>, remove: impl FnOnce(&mut Model, usize),
+    ) -> Option<usize> {
+        match self {
+            IndexingButtonsResponse::Delete(idx) => {
+                assert!(idx < data_vec(model).len());
+                remove(model, idx);
+                if idx < data_vec(model).len() {
+                    Some(idx)
+                } else {
+                    idx.checked_sub(1)
+                }
+            }
+            _ => self.apply(data_vec(model)),
+        }
+    }
 }
 
 impl UiState {
@@ -374,11 +410,26 @@ impl UiState {
     pub(crate) fn refresh_properties_panel(&mut self, model: &Model) {
         match self.tree_view_selection {
             TreeSelection::Header => {
+                // keep the density around between refreshes, its not part of the model
+                let (density, com_mode, num_cross_sections) =
+                    if let PropertiesPanel::Header { density, com_mode, num_cross_sections, .. } = self.properties_panel {
+                        (density, com_mode, num_cross_sections)
+                    } else {
+                        (DEFAULT_DENSITY, Default::default(), DEFAULT_NUM_CROSS_SECTIONS)
+                    };
+                let (lod_ratios_string, num_debris) = if let PropertiesPanel::Header { lod_ratios_string, num_debris, .. } = &self.properties_panel {
+                    (lod_ratios_string.clone(), *num_debris)
+                } else {
+                    (DEFAULT_LOD_RATIOS.to_string(), DEFAULT_NUM_DEBRIS)
+                };
                 self.properties_panel = PropertiesPanel::Header {
                     bbox_min_string: format!("{}", model.header.bbox.min),
                     bbox_max_string: format!("{}", model.header.bbox.max),
                     radius_string: format!("{}", model.header.max_radius),
                     mass_string: format!("{}", model.header.mass),
+                    com_string: format!("{}", model.header.center_of_mass),
+                    com_mode,
+                    num_cross_sections,
                     moir_string: format!(
                         "{:e}, {:e}, {:e}",
                         model.header.moment_of_inertia.rvec.x, model.header.moment_of_inertia.rvec.y, model.header.moment_of_inertia.rvec.z
@@ -392,6 +443,9 @@ impl UiState {
                         model.header.moment_of_inertia.fvec.x, model.header.moment_of_inertia.fvec.y, model.header.moment_of_inertia.fvec.z
                     ),
                     transform_window: Default::default(),
+                    density,
+                    lod_ratios_string,
+                    num_debris,
                 }
             }
             TreeSelection::SubObjects(subobj_tree_select) => match subobj_tree_select {
@@ -406,6 +460,8 @@ impl UiState {
                         name: format!("{}", model.sub_objects[id].name),
                         rot_axis: model.sub_objects[id].movement_axis,
                         transform_window: Default::default(),
+                        smoothing_angle: DEFAULT_SMOOTHING_ANGLE,
+                        hard_uv_seams: false,
                     }
                 }
             },
@@ -574,7 +630,28 @@ impl UiState {
                 }
                 _ => self.properties_panel = PropertiesPanel::default_eye(),
             },
-            TreeSelection::Shield => self.properties_panel = PropertiesPanel::Shield, // nothing mutable to refresh! woohoo!'
+            TreeSelection::Shield => {
+                // nothing mutable to refresh! woohoo!' just keep the generation options around and recheck the mesh
+                let (generation_options, max_hole_edges) =
+                    if let PropertiesPanel::Shield { generation_options, max_hole_edges, .. } = self.properties_panel {
+                        (generation_options, max_hole_edges)
+                    } else {
+                        (Default::default(), DEFAULT_MAX_HOLE_EDGES)
+                    };
+                let (open_edges, duplicate_edges) = match &model.shield_data {
+                    Some(shield_data) => {
+                        let report = shield_data.check_topology();
+                        (report.open_edges.len(), report.duplicate_edges.len())
+                    }
+                    None => (0, 0),
+                };
+                self.properties_panel = PropertiesPanel::Shield {
+                    generation_options,
+                    open_edges,
+                    duplicate_edges,
+                    max_hole_edges,
+                }
+            }
             TreeSelection::VisualCenter => self.properties_panel = PropertiesPanel::VisualCenter { position: format!("{}", model.visual_center) },
             TreeSelection::Comments => self.properties_panel = PropertiesPanel::Comments,
         }
@@ -608,9 +685,15 @@ pub(crate) enum PropertiesPanel {
         bbox_max_string: String,
         radius_string: String,
         mass_string: String,
+        com_string: String,
+        com_mode: CenterOfMassMode,
+        num_cross_sections: usize,
         moir_string: String,
         moiu_string: String,
         moif_string: String,
+        density: f32,
+        lod_ratios_string: String,
+        num_debris: usize,
         transform_window: TransformWindow,
     },
     SubObject {
@@ -622,6 +705,8 @@ pub(crate) enum PropertiesPanel {
         is_debris_check: bool,
         rot_axis: SubsysMovementAxis,
         transform_window: TransformWindow,
+        smoothing_angle: f32,
+        hard_uv_seams: bool,
     },
     Texture {
         texture_name: String,
@@ -676,7 +761,12 @@ pub(crate) enum PropertiesPanel {
         lod_string: String,
         offset_string: String,
     },
-    Shield,
+    Shield {
+        generation_options: ShieldGenerationOptions,
+        open_edges: usize,
+        duplicate_edges: usize,
+        max_hole_edges: usize,
+    },
     EyePoint {
         position_string: String,
         normal_string: String,
@@ -694,9 +784,15 @@ impl Default for PropertiesPanel {
             bbox_max_string: Default::default(),
             radius_string: Default::default(),
             mass_string: Default::default(),
+            com_string: Default::default(),
+            com_mode: Default::default(),
+            num_cross_sections: DEFAULT_NUM_CROSS_SECTIONS,
             moir_string: Default::default(),
             moiu_string: Default::default(),
             moif_string: Default::default(),
+            density: DEFAULT_DENSITY,
+            lod_ratios_string: DEFAULT_LOD_RATIOS.to_string(),
+            num_debris: DEFAULT_NUM_DEBRIS,
             transform_window: TransformWindow {
                 open: false,
                 vector: format!("1, 0, 0"),
@@ -724,6 +820,8 @@ impl PropertiesPanel {
                 axis_select: 0,
                 transform_type: TransformType::Rotate,
             },
+            smoothing_angle: DEFAULT_SMOOTHING_ANGLE,
+            hard_uv_seams: false,
         }
     }
     fn default_texture() -> Self {
@@ -819,10 +917,16 @@ impl PofToolsGui {
                 bbox_min_string,
                 bbox_max_string,
                 mass_string,
+                com_string,
+                com_mode,
+                num_cross_sections,
                 radius_string,
                 moir_string,
                 moiu_string,
                 moif_string,
+                density,
+                lod_ratios_string,
+                num_debris,
                 transform_window,
             } => {
                 ui.heading("Header");
@@ -907,12 +1011,47 @@ impl PofToolsGui {
                         self.model.recalc_mass();
                         properties_panel_dirty = true;
                     }
+                    if ui
+                        .button("From Volume")
+                        .on_hover_text(
+                            "Calculates the mass, center of mass and moment of inertia as a solid of the given density\n\
+                            If the detail0 meshes are not closed, the usual estimates are used instead",
+                        )
+                        .clicked()
+                    {
+                        self.model.recalc_mass_properties_from_volume(*density);
+                        properties_panel_dirty = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Density:");
+                    ui.add(DragValue::new(density).speed(0.01).clamp_range(0.0..=f32::MAX));
                 });
                 UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, Some(&mut self.model.header.mass), mass_string);
 
                 ui.horizontal(|ui| {
-                    ui.add(egui::Label::new("Moment of Inertia:"));
+                    ui.add(egui::Label::new("Center of Mass:"));
                     if ui.button("Recalculate").clicked() {
+                        self.model.recalc_center_of_mass(*com_mode);
+                        properties_panel_dirty = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.selectable_value(com_mode, CenterOfMassMode::Vertex, "Vertices")
+                        .on_hover_text("Every vertex is treated as an equal point mass");
+                    ui.separator();
+                    ui.selectable_value(com_mode, CenterOfMassMode::Area, "Surface")
+                        .on_hover_text("Mass is spread evenly over the surface area");
+                    ui.separator();
+                    ui.selectable_value(com_mode, CenterOfMassMode::Volume, "Volume").on_hover_text(
+                        "Mass is spread evenly over the enclosed volume\nIf the detail0 meshes are not closed, surface area is used instead",
+                    );
+                });
+                UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, Some(&mut self.model.header.center_of_mass), com_string);
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new("Moment of Inertia:"));
+                    if ui.button("Recalculate").on_hover_text("Calculated about the center of mass").clicked() {
                         self.model.recalc_moi();
                         properties_panel_dirty = true;
                     }
@@ -939,6 +1078,28 @@ impl PofToolsGui {
                     moif_string,
                 );
 
+                let mut display_cross_sections = false;
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new("Cross Sections:"));
+                    let response = ui.button("Recalculate");
+                    if response.clicked() {
+                        self.model.recalc_cross_sections(*num_cross_sections);
+                    }
+                    display_cross_sections |= response.hovered() || response.has_focus();
+
+                    let response = ui
+                        .add(DragValue::new(num_cross_sections).clamp_range(1..=100))
+                        .on_hover_text("Number of slices");
+                    display_cross_sections |= response.hovered() || response.has_focus();
+                });
+                let response = CollapsingHeader::new(format!("{} sections", self.model.header.cross_sections.len())).show(ui, |ui| {
+                    for &(depth, radius) in &self.model.header.cross_sections {
+                        ui.label(RichText::new(format!("Depth: {:.2}  Radius: {:.2}", depth, radius)).weak());
+                    }
+                });
+                display_cross_sections |= response.header_response.hovered();
+                self.ui_state.display_cross_sections = display_cross_sections;
+
                 if radius_changed {
                     PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, One(Warning::RadiusTooSmall(None)));
                 }
@@ -979,6 +1140,61 @@ impl PofToolsGui {
                 }
                 ui.label(RichText::new(format!("Total vertices: {}", num_verts)).weak());
                 ui.label(RichText::new(format!("Total normals: {}", num_norms)).weak());
+
+                let oversized = self
+                    .errors
+                    .iter()
+                    .any(|error| matches!(error, Error::TooManyVerts(_) | Error::TooManyNorms(_)));
+                if ui
+                    .add_enabled(oversized, egui::Button::new("Split Oversized Subobjects"))
+                    .on_hover_text("Splits any subobject with too many vertices or normals for the selected version into child chunks which each fit")
+                    .clicked()
+                {
+                    buffer_ids_to_rebuild.extend(self.model.split_oversized_subobjects());
+                    PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                    self.ui_state.viewport_3d_dirty = true;
+                    properties_panel_dirty = true;
+                }
+
+                ui.add_space(10.0);
+
+                let lod_ratios = lod_ratios_string
+                    .split(',')
+                    .map(|ratio| ratio.trim().parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>();
+                let lod_ratios = lod_ratios.ok().filter(|ratios| ratios.iter().all(|&ratio| ratio > 0.0 && ratio <= 1.0));
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(lod_ratios.is_some() && !self.model.header.detail_levels.is_empty(), egui::Button::new("Generate LODs"))
+                        .on_hover_text("Adds a detail level for each ratio, made from a copy of detail0 simplified to that fraction of its triangles")
+                        .clicked()
+                    {
+                        self.model.generate_lods(lod_ratios.as_ref().unwrap());
+                        PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                        self.ui_state.viewport_3d_dirty = true;
+                        properties_panel_dirty = true;
+                    }
+                    ui.add(TextEdit::singleline(lod_ratios_string).desired_width(100.0))
+                        .on_hover_text("Comma separated ratios between 0 and 1");
+                });
+
+                let debris_left = (pof::MAX_DEBRIS_OBJECTS - self.model.num_debris_objects().min(pof::MAX_DEBRIS_OBJECTS)) as usize;
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(debris_left > 0 && !self.model.header.detail_levels.is_empty(), egui::Button::new("Generate Debris"))
+                        .on_hover_text(format!(
+                            "Breaks a copy of detail0 up into pieces, which are added as debris subobjects\n{} more debris subobjects can be added",
+                            debris_left
+                        ))
+                        .clicked()
+                    {
+                        self.model.generate_debris(*num_debris);
+                        PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                        self.ui_state.viewport_3d_dirty = true;
+                        properties_panel_dirty = true;
+                    }
+                    ui.add(DragValue::new(num_debris).clamp_range(1..=pof::MAX_DEBRIS_OBJECTS as usize));
+                });
             }
             PropertiesPanel::SubObject {
                 bbox_min_string,
@@ -989,11 +1205,13 @@ impl PofToolsGui {
                 is_debris_check,
                 rot_axis,
                 transform_window,
+                smoothing_angle,
+                hard_uv_seams,
             } => {
                 ui.heading("SubObject");
                 ui.separator();
 
-                let selected_id = if let TreeSelection::SubObjects(SubObjectSelection::SubObject(id)) = self.ui_state.tree_view_selection {
+                let mut selected_id = if let TreeSelection::SubObjects(SubObjectSelection::SubObject(id)) = self.ui_state.tree_view_selection {
                     Some(id)
                 } else {
                     None
@@ -1202,6 +1420,205 @@ impl PofToolsGui {
                     }
                 }
 
+                // Triangulation buttons ================================================================
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Triangulate"))
+                        .on_hover_text("Splits every polygon with more than 3 vertices into triangles")
+                        .clicked()
+                        && self.model.sub_objects[selected_id.unwrap()].triangulate_all() > 0
+                    {
+                        self.ui_state.viewport_3d_dirty = true;
+                        buffer_ids_to_rebuild.push(selected_id.unwrap());
+                    }
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Split Non-Planar"))
+                        .on_hover_text("Splits only the polygons whose vertices do not lie on a single plane into triangles")
+                        .clicked()
+                        && self.model.sub_objects[selected_id.unwrap()].split_non_planar_polygons(NON_PLANAR_TOLERANCE) > 0
+                    {
+                        self.ui_state.viewport_3d_dirty = true;
+                        buffer_ids_to_rebuild.push(selected_id.unwrap());
+                    }
+                });
+
+                // Normals recalculation ================================================================
+
+                ui.horizontal(|ui| {
+                    ui.label("Normals:");
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Recalculate"))
+                        .on_hover_text("Rebuilds the normals, smoothing between faces within the smoothing angle of each other\nA smoothing angle of 0 gives flat shading")
+                        .clicked()
+                    {
+                        self.model.sub_objects[selected_id.unwrap()].recalc_normals(smoothing_angle.to_radians(), *hard_uv_seams);
+                        self.ui_state.viewport_3d_dirty = true;
+                        buffer_ids_to_rebuild.push(selected_id.unwrap());
+                        PofToolsGui::recheck_errors(&mut self.errors, &self.model, One(Error::TooManyNorms(selected_id.unwrap())));
+                    }
+                });
+                ui.add_enabled_ui(selected_id.is_some(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Smoothing Angle:");
+                        ui.add(DragValue::new(smoothing_angle).speed(0.5).clamp_range(0.0..=180.0).suffix("°"));
+                    });
+                    ui.checkbox(hard_uv_seams, "Hard Edges at UV Seams");
+                });
+
+                // Convex hull buttons ================================================================
+
+                ui.horizontal(|ui| {
+                    ui.label("Convex Hull:");
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Replace Geometry"))
+                        .on_hover_text("Replaces this subobject's geometry with its convex hull")
+                        .clicked()
+                    {
+                        let subobj = &mut self.model.sub_objects[selected_id.unwrap()];
+                        if let Some(bsp_data) = subobj.generate_convex_hull() {
+                            subobj.bsp_data = bsp_data;
+                            subobj.recalc_bbox();
+                            subobj.recalc_radius();
+                            buffer_ids_to_rebuild.push(selected_id.unwrap());
+                            PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                    }
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Add Collision Hull"))
+                        .on_hover_text(
+                            "Adds the convex hull as an invisible child subobject which handles collisions in place of this one\n\
+                            Uses $collide_invisible and $nocollide_this_only",
+                        )
+                        .clicked()
+                    {
+                        if let Some(new_id) = self.model.add_collision_hull(selected_id.unwrap()) {
+                            buffer_ids_to_rebuild.push(new_id);
+                            PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                    }
+                });
+
+                // Merge / split buttons ================================================================
+
+                ui.horizontal(|ui| {
+                    // detail levels must stay
+                    let mergeable = matches!(selected_id, Some(id) if !self.model.header.detail_levels.contains(&id));
+                    ui.add_enabled_ui(mergeable, |ui| {
+                        ui.menu_button("Merge Into...", |ui| {
+                            let id = selected_id.unwrap();
+                            for target in self.model.sub_objects.iter().map(|subobj| subobj.obj_id).collect::<Vec<_>>() {
+                                // can't merge into itself or one of its own descendants
+                                if self.model.is_obj_id_ancestor(target, id) {
+                                    continue;
+                                }
+                                if ui.button(&self.model.sub_objects[target].name).clicked() {
+                                    if let Some(new_id) = self.model.merge_subobjects(target, id) {
+                                        // every subobject after the removed one has shifted down, so all of their buffers need rebuilding
+                                        self.buffer_objects.truncate(id.0 as usize);
+                                        buffer_ids_to_rebuild.push(new_id);
+                                        self.ui_state.tree_view_selection = TreeSelection::SubObjects(SubObjectSelection::SubObject(new_id));
+                                        self.ui_state.last_selected_subobj = Some(new_id);
+                                        selected_id = Some(new_id);
+                                        PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
+                                        PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                                        self.ui_state.viewport_3d_dirty = true;
+                                        properties_panel_dirty = true;
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Moves this subobject's geometry and children into another, and removes it");
+                    });
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Split By Texture"))
+                        .on_hover_text("Moves the polygons of each texture (besides the most used one) into their own child subobject")
+                        .clicked()
+                    {
+                        let new_ids = self.model.split_subobject_by_texture(selected_id.unwrap());
+                        if !new_ids.is_empty() {
+                            buffer_ids_to_rebuild.push(selected_id.unwrap());
+                            PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                    }
+                    if ui
+                        .add_enabled(selected_id.is_some(), egui::Button::new("Separate Loose Parts"))
+                        .on_hover_text("Moves each disconnected piece (besides the largest one) into its own child subobject")
+                        .clicked()
+                    {
+                        let new_ids = self.model.separate_loose_parts(selected_id.unwrap());
+                        if !new_ids.is_empty() {
+                            buffer_ids_to_rebuild.push(selected_id.unwrap());
+                            PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                    }
+                });
+
+                if ui
+                    .add_enabled(selected_id.is_some(), egui::Button::new("Import Geometry..."))
+                    .on_hover_text(
+                        "Replaces this subobject's geometry with that of the node or object with the same name in a DAE, glTF or OBJ file\n\
+                        Everything else about the subobject is kept",
+                    )
+                    .clicked()
+                {
+                    if let Some(path) = PofToolsGui::pick_geometry_file() {
+                        let id = selected_id.unwrap();
+                        let name = self.model.sub_objects[id].name.clone();
+                        let num_textures = self.model.textures.len();
+                        let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("").to_string();
+                        let result = self
+                            .model
+                            .replace_subobject_geometry(id, path, &name, &self.ui_state.import_options)
+                            .map(|warnings| ((), warnings));
+                        if PofToolsGui::log_import_result(&filename, result).is_some() {
+                            if self.model.textures.len() != num_textures {
+                                // the texture count is baked into the buffers, so they all need rebuilding
+                                buffer_ids_to_rebuild.extend(self.model.sub_objects.iter().map(|subobj| subobj.obj_id));
+                                reload_textures = true;
+                            } else {
+                                buffer_ids_to_rebuild.push(id);
+                            }
+                            PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
+                            PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                    }
+                }
+
+                if ui
+                    .add_enabled(selected_id.is_some(), egui::Button::new("Delete Subobject"))
+                    .on_hover_text(
+                        "Deletes this subobject and all of its children, along with any turrets on them\n\
+                        Eye points, glow banks and docking bays attached to them are moved to its parent",
+                    )
+                    .clicked()
+                {
+                    let removed = self.model.remove_subobject(selected_id.unwrap());
+                    // every subobject after the first removed one has shifted down, so all of their buffers need rebuilding
+                    self.buffer_objects.truncate(removed[0].0 as usize);
+                    self.ui_state.tree_view_selection = TreeSelection::SubObjects(SubObjectSelection::Header);
+                    self.ui_state.last_selected_subobj = self.model.header.detail_levels.first().copied();
+                    selected_id = None;
+                    PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
+                    PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+                    self.ui_state.viewport_3d_dirty = true;
+                    properties_panel_dirty = true;
+                }
+
+                ui.separator();
+
                 // Parent subobject combo box ================================================================
 
                 // first index is none
@@ -1314,6 +1731,14 @@ impl PofToolsGui {
                 });
                 ui.separator();
 
+                let texture_num = match self.ui_state.tree_view_selection {
+                    TreeSelection::Textures(TextureSelection::Texture(tex)) => Some(tex.0 as usize),
+                    _ => None,
+                };
+                let texture_idx_response = UiState::list_manipulator_widget(ui, texture_num, Some(self.model.textures.len()), "Texture");
+
+                ui.add_space(10.0);
+
                 let tex = if let TreeSelection::Textures(TextureSelection::Texture(tex)) = self.ui_state.tree_view_selection {
                     Some(&mut self.model.textures[tex.0 as usize])
                 } else {
@@ -1328,6 +1753,25 @@ impl PofToolsGui {
                     self.model.untextured_idx = None;
                     PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, One(Warning::UntexturedPolygons));
                 }
+
+                if let Some(response) = texture_idx_response {
+                    let new_idx = response.apply_to_model(
+                        &mut self.model,
+                        |model| &mut model.textures,
+                        |model, idx| {
+                            model.remove_texture(TextureId(idx as u32));
+                        },
+                    );
+                    // texture ids are baked into the buffers, so they all need rebuilding
+                    buffer_ids_to_rebuild.extend(self.model.sub_objects.iter().map(|subobj| subobj.obj_id));
+                    PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
+                    PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
+
+                    self.ui_state.tree_view_selection = TreeSelection::Textures(TextureSelection::texture(new_idx));
+                    reload_textures = true;
+                    properties_panel_dirty = true;
+                    self.ui_state.viewport_3d_dirty = true;
+                }
             }
             PropertiesPanel::Thruster {
                 engine_subsys_string,
@@ -1498,8 +1942,15 @@ impl PofToolsGui {
                 .changed();
 
                 if let Some(response) = bank_idx_response {
-                    let (weapon_system, is_primary) = weapon_system.unwrap();
-                    let new_idx = response.apply(weapon_system);
+                    let (_, is_primary) = weapon_system.unwrap();
+                    let weapon_system: fn(&mut Model) -> &mut Vec<Vec<WeaponHardpoint>> = if is_primary {
+                        |model| &mut model.primary_weps
+                    } else {
+                        |model| &mut model.secondary_weps
+                    };
+                    let new_idx = response.apply_to_model(&mut self.model, weapon_system, |model, idx| {
+                        model.remove_weapon_bank(is_primary, idx);
+                    });
 
                     self.ui_state.tree_view_selection = TreeSelection::Weapons(WeaponSelection::bank(is_primary, new_idx));
                     properties_panel_dirty = true;
@@ -1801,7 +2252,13 @@ impl PofToolsGui {
                 UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, norm, normal_string);
 
                 if let Some(response) = bank_idx_response {
-                    let new_idx = response.apply(&mut self.model.glow_banks);
+                    let new_idx = response.apply_to_model(
+                        &mut self.model,
+                        |model| &mut model.glow_banks,
+                        |model, idx| {
+                            model.remove_glow_bank(idx);
+                        },
+                    );
 
                     self.ui_state.tree_view_selection = TreeSelection::Glows(GlowSelection::bank(new_idx));
                     properties_panel_dirty = true;
@@ -1898,7 +2355,14 @@ impl PofToolsGui {
                 UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, pos, position_string);
 
                 if let Some(response) = spec_point_idx_response {
-                    let new_idx = response.apply(&mut self.model.special_points);
+                    let new_idx = response.apply_to_model(
+                        &mut self.model,
+                        |model| &mut model.special_points,
+                        |model, idx| {
+                            model.remove_special_point(idx);
+                        },
+                    );
+                    PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
 
                     self.ui_state.tree_view_selection = TreeSelection::SpecialPoints(SpecialPointSelection::point(new_idx));
                     properties_panel_dirty = true;
@@ -1994,7 +2458,13 @@ impl PofToolsGui {
                 UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, pos, position_string);
 
                 if let Some(response) = turret_idx_response {
-                    let new_idx = response.apply(&mut self.model.turrets);
+                    let new_idx = response.apply_to_model(
+                        &mut self.model,
+                        |model| &mut model.turrets,
+                        |model, idx| {
+                            model.remove_turret(idx);
+                        },
+                    );
                     PofToolsGui::recheck_errors(&mut self.errors, &self.model, All);
 
                     self.ui_state.tree_view_selection = TreeSelection::Turrets(TurretSelection::turret(new_idx));
@@ -2057,11 +2527,14 @@ impl PofToolsGui {
                 UiState::model_value_edit(&mut self.ui_state.viewport_3d_dirty, ui, false, pos, position_string);
 
                 if let Some(response) = path_idx_response {
-                    if let IndexingButtonsResponse::Delete(idx) = response {
-                        self.model.path_removal_fixup(PathId(idx as u32));
-                        PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
-                    }
-                    let new_idx = response.apply(&mut self.model.paths);
+                    let new_idx = response.apply_to_model(
+                        &mut self.model,
+                        |model| &mut model.paths,
+                        |model, idx| {
+                            model.remove_path(PathId(idx as u32));
+                        },
+                    );
+                    PofToolsGui::recheck_warnings(&mut self.warnings, &self.model, All);
 
                     self.ui_state.tree_view_selection = TreeSelection::Paths(PathSelection::path(new_idx));
                     properties_panel_dirty = true;
@@ -2074,15 +2547,75 @@ impl PofToolsGui {
                     self.ui_state.viewport_3d_dirty = true;
                 }
             }
-            PropertiesPanel::Shield => {
+            PropertiesPanel::Shield {
+                generation_options,
+                open_edges,
+                duplicate_edges,
+                max_hole_edges,
+            } => {
                 ui.heading("Shield");
                 ui.separator();
-                if let Some(shield_data) = &self.model.shield_data {
+                if let Some(shield_data) = &mut self.model.shield_data {
                     ui.label(format!("{} vertices", shield_data.verts.len()));
                     ui.label(format!("{} polygons", shield_data.polygons.len()));
+                    if *open_edges == 0 && *duplicate_edges == 0 {
+                        ui.label("The shield is closed.");
+                    } else {
+                        ui.colored_label(Color32::YELLOW, format!("{} open edges", open_edges))
+                            .on_hover_text("Edges with no polygon on the other side, i.e. holes in the shield");
+                        ui.colored_label(Color32::YELLOW, format!("{} duplicated edges", duplicate_edges))
+                            .on_hover_text("Edges shared by polygons which wind the same direction, i.e. flipped or overlapping polygons");
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Repair")
+                            .on_hover_text("Flips polygons to face consistently outwards, and fills holes of up to the given number of edges")
+                            .clicked()
+                        {
+                            shield_data.repair(*max_hole_edges);
+                            self.buffer_shield = Some(GlBufferedShield::new(display, shield_data));
+                            self.ui_state.viewport_3d_dirty = true;
+                            properties_panel_dirty = true;
+                        }
+                        ui.label("Max Hole Edges:");
+                        ui.add(DragValue::new(max_hole_edges).clamp_range(3..=100));
+                    });
                 } else {
                     ui.label("This model has no shield mesh.");
                 }
+
+                ui.separator();
+
+                ui.label("Generate:");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut generation_options.shape, ShieldShape::ConvexHull, "Convex Hull")
+                        .on_hover_text("The convex hull of detail0, simplified to fit the triangle budget");
+                    ui.separator();
+                    ui.selectable_value(&mut generation_options.shape, ShieldShape::Ellipsoid, "Ellipsoid")
+                        .on_hover_text("An ellipsoid shrink-wrapped onto the convex hull of detail0");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max Triangles:");
+                    ui.add(DragValue::new(&mut generation_options.max_triangles).clamp_range(8..=5000));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Padding:");
+                    ui.add(DragValue::new(&mut generation_options.padding).speed(0.1));
+                });
+                if ui
+                    .add_enabled(!self.model.header.detail_levels.is_empty(), egui::Button::new("Generate Shield"))
+                    .on_hover_text("Replaces the current shield, if any")
+                    .clicked()
+                {
+                    if let Some(shield_data) = ShieldData::generate(&self.model, generation_options) {
+                        self.buffer_shield = Some(GlBufferedShield::new(display, &shield_data));
+                        self.model.shield_data = Some(shield_data);
+                        self.ui_state.viewport_3d_dirty = true;
+                        properties_panel_dirty = true;
+                    } else {
+                        error!("Failed to generate a shield: detail0 is too flat or has too little geometry to enclose");
+                    }
+                }
             }
             PropertiesPanel::Insignia { lod_string, offset_string } => {
                 ui.heading("Insignia");